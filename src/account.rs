@@ -0,0 +1,33 @@
+use octocrab::Octocrab;
+
+pub struct Account {
+  // GitHub login of the token owner when several accounts are configured,
+  // empty for a single account so existing dedupe keys stay valid.
+  pub label: String,
+  pub token: String,
+  pub octocrab: Octocrab,
+  pub login: Option<String>,
+  pub self_login: Option<String>,
+}
+
+impl Account {
+  pub fn dedupe_key(&self, key: &str) -> String {
+    if self.label.is_empty() {
+      key.to_string()
+    } else {
+      format!("{}:{key}", self.label)
+    }
+  }
+
+  pub fn display_name(&self, idx: usize) -> String {
+    if self.label.is_empty() {
+      format!("#{}", idx + 1)
+    } else {
+      self.label.clone()
+    }
+  }
+}
+
+pub fn find_account<'a>(accounts: &'a [Account], label: &str) -> Option<&'a Account> {
+  accounts.iter().find(|account| account.label == label)
+}
@@ -0,0 +1,165 @@
+use crate::db::NotificationStore;
+use crate::feed::{atom_feed, FEED_ENTRY_LIMIT};
+use crate::metrics::Metrics;
+use anyhow::{Context, Result};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{info, warn};
+
+pub struct AdminState {
+  pub store: Arc<dyn NotificationStore>,
+  pub scope_warnings: Vec<String>,
+  pub metrics: Arc<Metrics>,
+  pub started_at: i64,
+  // `/healthz` fails once the last successful poll, or startup before the
+  // first one, is older than this.
+  pub health_max_age: Duration,
+}
+
+impl AdminState {
+  fn is_healthy(&self, now: i64) -> bool {
+    let (last_success, _) = self.metrics.poll_status();
+    now - last_success.unwrap_or(self.started_at)
+      <= self.health_max_age.as_secs() as i64
+  }
+
+  fn is_ready(&self) -> bool {
+    self.metrics.poll_status().1
+  }
+}
+
+pub async fn bind(kind: &str, addr: SocketAddr) -> Result<TcpListener> {
+  let listener = TcpListener::bind(addr)
+    .await
+    .with_context(|| format!("bind {kind} server on {addr}"))?;
+  info!("{kind} server listening on {addr}");
+  Ok(listener)
+}
+
+pub async fn serve(listener: TcpListener, state: Arc<AdminState>) -> Result<()> {
+  loop {
+    let (stream, _) = listener.accept().await.context("accept admin connection")?;
+    let state = state.clone();
+    tokio::spawn(async move {
+      if let Err(err) = handle(stream, &state).await {
+        warn!("admin request failed: {err:#}");
+      }
+    });
+  }
+}
+
+async fn handle(mut stream: TcpStream, state: &AdminState) -> Result<()> {
+  let mut buf = [0u8; 2048];
+  let n = stream.read(&mut buf).await.context("read admin request")?;
+  let request = String::from_utf8_lossy(&buf[..n]);
+  let mut request_line = request.lines().next().unwrap_or("").split_whitespace();
+  let method = request_line.next().unwrap_or("");
+  let path = request_line.next().unwrap_or("");
+
+  let (status, content_type, body) = match (method, path) {
+    ("GET", "/healthz") => {
+      let healthy = state.is_healthy(chrono::Utc::now().timestamp());
+      let (status, mut body) = if healthy {
+        ("200 OK", String::from("ok\n"))
+      } else {
+        ("503 Service Unavailable", String::from("stale\n"))
+      };
+      for warning in &state.scope_warnings {
+        body.push_str(&format!("warning: {warning}\n"));
+      }
+      (status, "text/plain", body)
+    }
+    ("GET", "/ready") => {
+      if state.is_ready() {
+        ("200 OK", "text/plain", "ready\n".to_string())
+      } else {
+        (
+          "503 Service Unavailable",
+          "text/plain",
+          "starting\n".to_string(),
+        )
+      }
+    }
+    ("GET", "/feed.xml") => {
+      match state.store.recent_feed_entries(FEED_ENTRY_LIMIT).await {
+        Ok(entries) => ("200 OK", "application/atom+xml", atom_feed(&entries)),
+        Err(err) => {
+          warn!("load feed entries failed: {err:#}");
+          (
+            "500 Internal Server Error",
+            "text/plain",
+            "error\n".to_string(),
+          )
+        }
+      }
+    }
+    ("GET", "/metrics") => (
+      "200 OK",
+      "text/plain; version=0.0.4",
+      state.metrics.render(),
+    ),
+    _ => ("404 Not Found", "text/plain", "not found\n".to_string()),
+  };
+
+  let response = format!(
+    "HTTP/1.1 {status}\r\nContent-Type: {content_type}; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+    body.len()
+  );
+  stream
+    .write_all(response.as_bytes())
+    .await
+    .context("write admin response")?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::db::{connect_store, StoreOptions};
+
+  async fn state(metrics: Metrics) -> AdminState {
+    let options = StoreOptions {
+      database_url: "memory://".to_string(),
+      statement_timeout: None,
+      require_tls: false,
+      ca_cert: None,
+      max_retries: 0,
+      max_connections: 1,
+      connect_timeout: Duration::from_secs(1),
+    };
+    AdminState {
+      store: connect_store(&options).await.unwrap().into(),
+      scope_warnings: Vec::new(),
+      metrics: Arc::new(metrics),
+      started_at: 1_000,
+      health_max_age: Duration::from_secs(300),
+    }
+  }
+
+  #[tokio::test]
+  async fn health_follows_the_last_successful_poll() {
+    let starting = state(Metrics::default()).await;
+    assert!(starting.is_healthy(1_300));
+    assert!(!starting.is_healthy(1_301));
+    assert!(!starting.is_ready());
+
+    let metrics = Metrics::default();
+    metrics.record_poll(&Default::default(), Duration::from_secs(1), 2_000);
+    let polled = state(metrics).await;
+    assert!(polled.is_healthy(2_300));
+    assert!(!polled.is_healthy(2_301));
+    assert!(polled.is_ready());
+  }
+
+  #[tokio::test]
+  async fn a_failed_first_poll_is_ready_but_not_healthy_for_long() {
+    let metrics = Metrics::default();
+    metrics.record_failed_poll(Duration::from_secs(1));
+    let failed = state(metrics).await;
+    assert!(failed.is_ready());
+    assert!(!failed.is_healthy(1_301));
+  }
+}
@@ -0,0 +1,48 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Delay after `failures` consecutive failed polls: the poll interval doubled
+// per failure, capped at `max` but never below the interval itself.
+pub fn poll_backoff(interval: Duration, max: Duration, failures: u32) -> Duration {
+  let delay = interval.saturating_mul(1 << failures.min(16));
+  delay.min(max).max(interval)
+}
+
+// Adds up to 10% so several instances failing together don't retry in step.
+pub fn with_jitter(delay: Duration) -> Duration {
+  let nanos = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|elapsed| elapsed.subsec_nanos() as u64)
+    .unwrap_or(0);
+  let spread_ms = delay.as_millis() as u64 / 10;
+  delay + Duration::from_millis(nanos % (spread_ms + 1))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn backoff_doubles_up_to_the_ceiling() {
+    let (interval, max) = (Duration::from_secs(60), Duration::from_secs(600));
+    let delays: Vec<_> = (0..6)
+      .map(|failures| poll_backoff(interval, max, failures).as_secs())
+      .collect();
+    assert_eq!(delays, [60, 120, 240, 480, 600, 600]);
+    assert_eq!(poll_backoff(interval, max, u32::MAX), max);
+  }
+
+  #[test]
+  fn backoff_never_drops_below_the_interval() {
+    let interval = Duration::from_secs(60);
+    assert_eq!(poll_backoff(interval, Duration::from_secs(10), 3), interval);
+  }
+
+  #[test]
+  fn jitter_adds_at_most_a_tenth() {
+    let delay = Duration::from_secs(10);
+    for _ in 0..100 {
+      let jittered = with_jitter(delay);
+      assert!(jittered >= delay && jittered <= delay + Duration::from_secs(1));
+    }
+  }
+}
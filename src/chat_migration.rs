@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+// Chat ids Telegram moved after a group was upgraded to a supergroup, keyed
+// by the configured id. The old id rejects every send from then on.
+#[derive(Debug, Clone, Default)]
+pub struct ChatMigrations {
+  entries: Arc<Mutex<HashMap<String, Migration>>>,
+}
+
+#[derive(Debug)]
+struct Migration {
+  chat_id: String,
+  persisted: bool,
+}
+
+impl ChatMigrations {
+  pub fn resolve(&self, chat_id: &str) -> String {
+    self
+      .entries
+      .lock()
+      .ok()
+      .and_then(|entries| entries.get(chat_id).map(|m| m.chat_id.clone()))
+      .unwrap_or_else(|| chat_id.to_string())
+  }
+
+  pub fn record(&self, from: &str, to: &str, persisted: bool) {
+    if let Ok(mut entries) = self.entries.lock() {
+      entries.insert(
+        from.to_string(),
+        Migration {
+          chat_id: to.to_string(),
+          persisted,
+        },
+      );
+    }
+  }
+
+  // Every chat id a configured one moved to.
+  pub fn chat_ids(&self) -> Vec<String> {
+    self
+      .entries
+      .lock()
+      .map(|entries| entries.values().map(|m| m.chat_id.clone()).collect())
+      .unwrap_or_default()
+  }
+
+  // Migrations not yet written to the meta table, marked persisted on return.
+  pub fn take_unpersisted(&self) -> Vec<(String, String)> {
+    let Ok(mut entries) = self.entries.lock() else {
+      return Vec::new();
+    };
+    entries
+      .iter_mut()
+      .filter(|(_, m)| !m.persisted)
+      .map(|(from, m)| {
+        m.persisted = true;
+        (from.clone(), m.chat_id.clone())
+      })
+      .collect()
+  }
+}
+
+pub fn migration_meta_key(chat_id: &str) -> String {
+  format!("telegram_chat_migrated:{chat_id}")
+}
+
+// Telegram reports the upgrade as a 400 whose body carries
+// `parameters.migrate_to_chat_id`.
+pub fn migrate_to_chat_id(body: &str) -> Option<String> {
+  let body: serde_json::Value = serde_json::from_str(body).ok()?;
+  match &body["parameters"]["migrate_to_chat_id"] {
+    serde_json::Value::Number(id) => Some(id.to_string()),
+    serde_json::Value::String(id) => Some(id.clone()),
+    _ => None,
+  }
+}
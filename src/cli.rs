@@ -0,0 +1,79 @@
+use crate::duration::parse_duration;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+const DEFAULT_MUTE: Duration = Duration::from_secs(24 * 60 * 60);
+
+pub enum Command {
+  Run,
+  Stats { since: Option<DateTime<Utc>> },
+  FormatDiff { since: Option<DateTime<Utc>> },
+  ResetDedupe,
+  Mute { repo: String, duration: Duration },
+  Audit { since: Option<DateTime<Utc>> },
+}
+
+pub fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Command> {
+  let mut command = Command::Run;
+  let mut since = None;
+  let mut confirmed = false;
+  let mut mute_for = None;
+
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--stats" => command = Command::Stats { since: None },
+      "--format-diff" => command = Command::FormatDiff { since: None },
+      "--audit" => command = Command::Audit { since: None },
+      "--reset-dedupe" => command = Command::ResetDedupe,
+      "--yes" => confirmed = true,
+      "--mute" => {
+        let repo = args.next().context("--mute requires owner/repo")?;
+        command = Command::Mute {
+          repo,
+          duration: DEFAULT_MUTE,
+        };
+      }
+      "--for" => {
+        let raw = args.next().context("--for requires a duration")?;
+        mute_for = Some(parse_duration(&raw)?);
+      }
+      "--since" => {
+        let raw = args.next().context("--since requires a timestamp")?;
+        since = Some(parse_timestamp(&raw)?);
+      }
+      other => bail!("unknown argument: {other}"),
+    }
+  }
+
+  match &mut command {
+    Command::Stats { since: slot }
+    | Command::FormatDiff { since: slot }
+    | Command::Audit { since: slot } => {
+      *slot = since;
+    }
+    _ if since.is_some() => {
+      bail!("--since is only valid with --stats, --format-diff or --audit");
+    }
+    Command::ResetDedupe if !confirmed => {
+      bail!("--reset-dedupe clears all dedupe state, pass --yes to confirm");
+    }
+    Command::Mute { duration, .. } => {
+      if let Some(mute_for) = mute_for.take() {
+        *duration = mute_for;
+      }
+    }
+    Command::Run | Command::ResetDedupe => {}
+  }
+  if mute_for.is_some() {
+    bail!("--for is only valid with --mute");
+  }
+
+  Ok(command)
+}
+
+fn parse_timestamp(raw: &str) -> Result<DateTime<Utc>> {
+  DateTime::parse_from_rfc3339(raw)
+    .map(|ts| ts.with_timezone(&Utc))
+    .with_context(|| format!("invalid --since timestamp, expected RFC3339: {raw}"))
+}
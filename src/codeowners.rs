@@ -0,0 +1,249 @@
+use octocrab::models::activity::Notification as GitHubNotification;
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+const CODEOWNERS_PATHS: &[&str] =
+  &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+const MAX_CACHED_PULLS: usize = 1000;
+
+pub struct CodeownersRule {
+  pattern: String,
+  owners: Vec<String>,
+}
+
+pub fn parse_codeowners(raw: &str) -> Vec<CodeownersRule> {
+  raw
+    .lines()
+    .map(|line| line.split('#').next().unwrap_or("").trim())
+    .filter(|line| !line.is_empty())
+    .filter_map(|line| {
+      let mut parts = line.split_whitespace();
+      let pattern = parts.next()?.to_string();
+      let owners = parts
+        .map(|owner| owner.trim_start_matches('@').to_ascii_lowercase())
+        .collect();
+      Some(CodeownersRule { pattern, owners })
+    })
+    .collect()
+}
+
+// As in GitHub, the last matching rule decides a file's owners. Only direct
+// user entries count; team entries would need a membership lookup per team.
+pub fn owns_any(rules: &[CodeownersRule], login: &str, files: &[String]) -> bool {
+  let login = login.to_ascii_lowercase();
+  files.iter().any(|file| {
+    rules
+      .iter()
+      .rev()
+      .find(|rule| path_matches(&rule.pattern, file))
+      .is_some_and(|rule| rule.owners.contains(&login))
+  })
+}
+
+// Supports the common gitignore subset: `*`, `**`, a leading `/` anchoring to
+// the repo root, a trailing `/` for directories, and bare names matching at
+// any depth.
+pub fn path_matches(pattern: &str, path: &str) -> bool {
+  let anchored =
+    pattern.starts_with('/') || pattern.trim_end_matches('/').contains('/');
+  let directory = pattern.ends_with('/');
+  let pattern = pattern.trim_matches('/');
+  let pattern_parts: Vec<&str> = pattern.split('/').collect();
+  let path_parts: Vec<&str> = path.split('/').collect();
+
+  let starts: Vec<usize> = if anchored {
+    vec![0]
+  } else {
+    (0..path_parts.len()).collect()
+  };
+  starts.into_iter().any(|start| {
+    let rest = &path_parts[start..];
+    match_prefix(&pattern_parts, rest, directory)
+  })
+}
+
+fn match_prefix(pattern: &[&str], path: &[&str], directory: bool) -> bool {
+  match (pattern.first(), path.first()) {
+    // A fully matched pattern owns everything below it; a directory pattern
+    // must not match the file itself.
+    (None, _) => !(directory && path.is_empty()),
+    (Some(&"**"), _) => {
+      (0..=path.len()).any(|skip| match_prefix(&pattern[1..], &path[skip..], directory))
+    }
+    (Some(_), None) => false,
+    (Some(segment), Some(part)) => {
+      glob_segment(segment, part) && match_prefix(&pattern[1..], &path[1..], directory)
+    }
+  }
+}
+
+fn glob_segment(pattern: &str, text: &str) -> bool {
+  match pattern.split_once('*') {
+    None => pattern == text,
+    Some((head, tail)) => {
+      let Some(rest) = text.strip_prefix(head) else {
+        return false;
+      };
+      (0..=rest.len())
+        .filter(|&idx| rest.is_char_boundary(idx))
+        .any(|idx| glob_segment(tail, &rest[idx..]))
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct PullFile {
+  filename: String,
+}
+
+// Whether a pull request touches files owned by the login, keyed by PR and
+// update time so a new push is checked again. Costs a CODEOWNERS fetch and a
+// files listing per uncached PR.
+#[derive(Default)]
+pub struct CodeownersCache {
+  entries: Mutex<HashMap<String, bool>>,
+}
+
+impl CodeownersCache {
+  pub async fn is_codeowner(
+    &self,
+    http: &Client,
+    github_token: &str,
+    login: &str,
+    n: &GitHubNotification,
+  ) -> bool {
+    if n.subject.r#type != "PullRequest" {
+      return false;
+    }
+    let Some(pull_url) = &n.subject.url else {
+      return false;
+    };
+    let key = format!("{pull_url}@{}", n.updated_at.timestamp());
+    if let Some(owned) = self.entries.lock().ok().and_then(|e| e.get(&key).copied()) {
+      return owned;
+    }
+
+    let Some(owned) = resolve_ownership(http, github_token, login, n).await else {
+      return false;
+    };
+    if let Ok(mut entries) = self.entries.lock() {
+      if entries.len() >= MAX_CACHED_PULLS {
+        entries.clear();
+      }
+      entries.insert(key, owned);
+    }
+    owned
+  }
+}
+
+async fn resolve_ownership(
+  http: &Client,
+  github_token: &str,
+  login: &str,
+  n: &GitHubNotification,
+) -> Option<bool> {
+  let pull_url = n.subject.url.as_ref()?;
+  let repo_url = n.repository.url.as_str();
+
+  let mut codeowners = None;
+  for path in CODEOWNERS_PATHS {
+    let url = format!("{repo_url}/contents/{path}");
+    if let Some(raw) =
+      get_text(http, github_token, &url, "application/vnd.github.raw").await
+    {
+      codeowners = Some(raw);
+      break;
+    }
+  }
+  let Some(codeowners) = codeowners else {
+    return Some(false);
+  };
+  let rules = parse_codeowners(&codeowners);
+
+  let files_url = format!("{pull_url}/files?per_page=100");
+  let raw = get_text(
+    http,
+    github_token,
+    &files_url,
+    "application/vnd.github+json",
+  )
+  .await?;
+  let files: Vec<PullFile> = serde_json::from_str(&raw).ok()?;
+  let files: Vec<String> = files.into_iter().map(|file| file.filename).collect();
+
+  Some(owns_any(&rules, login, &files))
+}
+
+async fn get_text(
+  http: &Client,
+  github_token: &str,
+  url: &str,
+  accept: &str,
+) -> Option<String> {
+  let response = http
+    .get(url)
+    .bearer_auth(github_token)
+    .header("Accept", accept)
+    .send()
+    .await
+    .ok()?;
+
+  if !response.status().is_success() {
+    return None;
+  }
+
+  response.text().await.ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "\
+# Default owners
+*           @octocat
+/docs/      @writer   # docs team lead
+*.rs        @rustacean @Octocat
+src/db/**   @dba
+/build.rs   @release
+";
+
+  fn owners(path: &str) -> Vec<String> {
+    parse_codeowners(SAMPLE)
+      .iter()
+      .rev()
+      .find(|rule| path_matches(&rule.pattern, path))
+      .map(|rule| rule.owners.clone())
+      .unwrap_or_default()
+  }
+
+  #[test]
+  fn last_matching_rule_owns_the_file() {
+    assert_eq!(owners("README.md"), ["octocat"]);
+    assert_eq!(owners("docs/guide.md"), ["writer"]);
+    assert_eq!(owners("src/main.rs"), ["rustacean", "octocat"]);
+    assert_eq!(owners("src/db/sqlite/schema.sql"), ["dba"]);
+    assert_eq!(owners("build.rs"), ["release"]);
+    assert_eq!(owners("tools/build.rs"), ["rustacean", "octocat"]);
+  }
+
+  #[test]
+  fn directory_patterns_match_below_not_the_name_itself() {
+    assert!(path_matches("/docs/", "docs/a/b.md"));
+    assert!(!path_matches("/docs/", "docs"));
+    assert!(!path_matches("/docs/", "site/docs/a.md"));
+    assert!(path_matches("logs/", "app/logs/today.txt"));
+  }
+
+  #[test]
+  fn codeowner_check_needs_a_directly_owned_file() {
+    let rules = parse_codeowners(SAMPLE);
+    let files = ["README.md".to_string(), "src/db/pool.rs".to_string()];
+    assert!(owns_any(&rules, "dba", &files));
+    assert!(owns_any(&rules, "OctoCat", &files));
+    assert!(!owns_any(&rules, "rustacean", &files));
+    assert!(!owns_any(&rules, "writer", &files));
+  }
+}
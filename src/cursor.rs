@@ -0,0 +1,232 @@
+use crate::db::PageValidators;
+use crate::duration::parse_duration;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use octocrab::models::activity::Notification as GitHubNotification;
+use std::collections::HashMap;
+
+// Where the next fetch for one account resumes. GitHub's `since` only has
+// second granularity, so the request still overlaps the previous poll by a
+// second as a safety net, and threads already handled at exactly the same
+// `updated_at` inside that overlap are dropped before dedupe. The validators
+// make the fetch conditional, so an unchanged inbox costs a 304.
+#[derive(Debug, Clone, Default)]
+pub struct SinceCursor {
+  latest: Option<DateTime<Utc>>,
+  handled: HashMap<String, DateTime<Utc>>,
+  validators: PageValidators,
+}
+
+impl SinceCursor {
+  // Restored from the store; the handled set isn't persisted, so the first
+  // poll after a restart relies on dedupe for the overlapping second.
+  pub fn resume(latest: DateTime<Utc>) -> Self {
+    Self {
+      latest: Some(latest),
+      ..Self::default()
+    }
+  }
+
+  pub fn latest(&self) -> Option<DateTime<Utc>> {
+    self.latest
+  }
+
+  pub fn api_since(&self) -> Option<DateTime<Utc>> {
+    self.latest.map(|latest| latest - Duration::seconds(1))
+  }
+
+  // While a re-forward is pending the fetch has to reach back to its start,
+  // which is usually older than the cursor.
+  pub fn api_since_from(
+    &self,
+    reforward_since: Option<DateTime<Utc>>,
+  ) -> Option<DateTime<Utc>> {
+    match (self.api_since(), reforward_since) {
+      (Some(since), Some(reforward)) => Some(since.min(reforward)),
+      (since, _) => since,
+    }
+  }
+
+  pub fn validators(&self) -> &PageValidators {
+    &self.validators
+  }
+
+  pub fn set_validators(&mut self, validators: PageValidators) {
+    self.validators = validators;
+  }
+
+  pub fn is_new(&self, n: &GitHubNotification) -> bool {
+    self.handled.get(&n.id.to_string()) != Some(&n.updated_at)
+  }
+
+  pub fn advanced<'a>(
+    &self,
+    covered: impl Iterator<Item = &'a GitHubNotification>,
+  ) -> Self {
+    let mut next = self.clone();
+    for n in covered {
+      next.latest = next.latest.max(Some(n.updated_at));
+      next.handled.insert(n.id.to_string(), n.updated_at);
+    }
+    if let Some(since) = next.api_since() {
+      next.handled.retain(|_, updated_at| *updated_at >= since);
+    }
+    next
+  }
+}
+
+// INITIAL_SINCE: an RFC 3339 timestamp, or a duration like `48h` counted back
+// from startup.
+pub fn parse_initial_since(raw: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+  if let Ok(ts) = DateTime::parse_from_rfc3339(raw) {
+    return Ok(ts.with_timezone(&Utc));
+  }
+  let Ok(ago) = parse_duration(raw) else {
+    bail!(
+      "invalid INITIAL_SINCE: {raw}, expected an RFC 3339 timestamp or a duration \
+       like 24h or 7d"
+    );
+  };
+  match Duration::from_std(ago)
+    .ok()
+    .and_then(|ago| now.checked_sub_signed(ago))
+  {
+    Some(since) => Ok(since),
+    None => bail!("invalid INITIAL_SINCE: {raw}, too far in the past"),
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorMode {
+  Time,
+  // Every poll pages through all unread notifications and relies on the
+  // dedupe store alone, so old unread threads are forwarded too.
+  UnreadSweep,
+}
+
+impl CursorMode {
+  pub fn parse(raw: &str) -> Result<Self> {
+    match raw.to_ascii_lowercase().as_str() {
+      "time" => Ok(Self::Time),
+      "unread_sweep" => Ok(Self::UnreadSweep),
+      _ => bail!("invalid CURSOR_MODE: {raw}, use time or unread_sweep"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn ts(raw: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(raw)
+      .unwrap()
+      .with_timezone(&Utc)
+  }
+
+  #[test]
+  fn reforward_reaches_back_past_the_cursor() {
+    let cursor = SinceCursor::resume(ts("2024-01-02T00:00:00Z"));
+    let reforward = ts("2024-01-01T00:00:00Z");
+    assert_eq!(cursor.api_since_from(Some(reforward)), Some(reforward));
+    assert_eq!(
+      cursor.api_since_from(None),
+      Some(ts("2024-01-01T23:59:59Z"))
+    );
+  }
+
+  #[test]
+  fn reforward_never_narrows_the_fetch() {
+    let cursor = SinceCursor::resume(ts("2024-01-02T00:00:00Z"));
+    assert_eq!(
+      cursor.api_since_from(Some(ts("2024-01-03T00:00:00Z"))),
+      Some(ts("2024-01-01T23:59:59Z"))
+    );
+    assert_eq!(
+      SinceCursor::default().api_since_from(Some(ts("2024-01-01T00:00:00Z"))),
+      None
+    );
+  }
+
+  fn thread(id: u64, updated_at: &str) -> GitHubNotification {
+    serde_json::from_value(serde_json::json!({
+      "id": id.to_string(),
+      "repository": {"id": 1, "name": "repo", "url": "https://api.github.com/repos/octo/repo"},
+      "subject": {"title": "t", "url": null, "latest_comment_url": null, "type": "Issue"},
+      "reason": "mention",
+      "unread": true,
+      "updated_at": updated_at,
+      "last_read_at": null,
+      "url": format!("https://api.github.com/notifications/threads/{id}"),
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn threads_handled_in_the_same_second_are_not_new_again() {
+    let first = [
+      thread(1, "2024-01-01T00:00:10Z"),
+      thread(2, "2024-01-01T00:00:10Z"),
+    ];
+    let cursor = SinceCursor::default().advanced(first.iter());
+    assert_eq!(cursor.latest(), Some(ts("2024-01-01T00:00:10Z")));
+    assert_eq!(cursor.api_since(), Some(ts("2024-01-01T00:00:09Z")));
+
+    // The overlapping second comes back with one more thread in it.
+    let second = [
+      thread(1, "2024-01-01T00:00:10Z"),
+      thread(2, "2024-01-01T00:00:10Z"),
+      thread(3, "2024-01-01T00:00:10Z"),
+    ];
+    let new: Vec<_> = second
+      .iter()
+      .filter(|n| cursor.is_new(n))
+      .map(|n| n.id.to_string())
+      .collect();
+    assert_eq!(new, ["3"]);
+    // A thread updated again inside the same second is new.
+    assert!(cursor.is_new(&thread(1, "2024-01-01T00:00:10.500Z")));
+  }
+
+  #[test]
+  fn handled_threads_outside_the_overlap_are_forgotten() {
+    let cursor = SinceCursor::default()
+      .advanced([thread(1, "2024-01-01T00:00:10Z")].iter())
+      .advanced([thread(2, "2024-01-01T00:00:20Z")].iter());
+    assert!(cursor.is_new(&thread(1, "2024-01-01T00:00:10Z")));
+    assert!(!cursor.is_new(&thread(2, "2024-01-01T00:00:20Z")));
+  }
+
+  #[test]
+  fn cursor_modes_parse_case_insensitively() {
+    assert_eq!(CursorMode::parse("time").unwrap(), CursorMode::Time);
+    assert_eq!(
+      CursorMode::parse("Unread_Sweep").unwrap(),
+      CursorMode::UnreadSweep
+    );
+    assert!(CursorMode::parse("sweep").is_err());
+  }
+
+  #[test]
+  fn initial_since_takes_a_timestamp_or_a_duration() {
+    let now = ts("2024-01-10T12:00:00Z");
+    assert_eq!(
+      parse_initial_since("2024-01-01T00:00:00Z", now).unwrap(),
+      ts("2024-01-01T00:00:00Z")
+    );
+    assert_eq!(
+      parse_initial_since("2024-01-01T08:00:00+08:00", now).unwrap(),
+      ts("2024-01-01T00:00:00Z")
+    );
+    assert_eq!(
+      parse_initial_since("48h", now).unwrap(),
+      ts("2024-01-08T12:00:00Z")
+    );
+    assert_eq!(
+      parse_initial_since("7d", now).unwrap(),
+      ts("2024-01-03T12:00:00Z")
+    );
+    let err = parse_initial_since("yesterday", now).unwrap_err();
+    assert!(err.to_string().contains("invalid INITIAL_SINCE: yesterday"));
+  }
+}
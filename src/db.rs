@@ -1,149 +1,3398 @@
 use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
-use sqlx::postgres::PgPoolOptions;
-use sqlx::sqlite::SqlitePoolOptions;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
 use sqlx::{PgPool, SqlitePool};
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+mod memory;
+#[cfg(feature = "mysql")]
+mod mysql;
 
 #[async_trait]
 pub trait NotificationStore: Send + Sync {
   async fn init(&self) -> Result<()>;
-  async fn is_sent(&self, id: &str) -> Result<bool>;
-  async fn mark_sent(&self, id: &str) -> Result<()>;
+  // One row per id and chat holds the latest updated_at sent; an id counts as
+  // sent for that timestamp and anything older.
+  async fn is_sent(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<bool>;
+  async fn mark_sent(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<()>;
+  // Records the id as sent before sending, returning false when it already
+  // was, so only one of several instances sharing a database sends it.
+  async fn try_claim(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<bool>;
+  async fn release_claim(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<()>;
+  async fn record_poll_stats(&self, stats: &PollStats) -> Result<()>;
+  async fn poll_stats_since(&self, since: DateTime<Utc>) -> Result<PollStatsSummary>;
+  async fn sent_stats(&self, since: DateTime<Utc>) -> Result<SentStats>;
+  // Holding a thread again replaces its payload with the newer update but
+  // keeps the original held_at.
+  async fn hold_pending(
+    &self,
+    id: &str,
+    payload: &str,
+    html_url: Option<&str>,
+    chat_id: Option<&str>,
+  ) -> Result<()>;
+  async fn list_pending(&self) -> Result<Vec<PendingNotification>>;
+  async fn remove_pending(&self, id: &str) -> Result<()>;
+  async fn get_meta(&self, key: &str) -> Result<Option<String>>;
+  async fn set_meta(&self, key: &str, value: &str) -> Result<()>;
+  async fn upsert_escalation(&self, escalation: &Escalation) -> Result<()>;
+  async fn due_escalations(&self, now: i64) -> Result<Vec<Escalation>>;
+  async fn remove_escalation(&self, account: &str, thread_id: &str) -> Result<()>;
+  async fn record_feed_entry(&self, entry: &FeedEntry, keep: i64) -> Result<()>;
+  async fn recent_feed_entries(&self, limit: i64) -> Result<Vec<FeedEntry>>;
+  async fn reset(&self) -> Result<u64>;
+  async fn mute_repo(&self, repo: &str, until: i64) -> Result<()>;
+  async fn active_mutes(&self, now: i64) -> Result<Vec<(String, i64)>>;
+  async fn read_state(&self, thread_id: &str) -> Result<Option<bool>>;
+  async fn set_read_state(&self, thread_id: &str, unread: bool) -> Result<()>;
+  async fn audit(&self, entry: &AuditEntry) -> Result<()>;
+  async fn audit_since(&self, since: i64) -> Result<Vec<AuditEntry>>;
+  async fn load_cursor(&self, account: &str) -> Result<Option<DateTime<Utc>>>;
+  async fn save_cursor(&self, account: &str, ts: DateTime<Utc>) -> Result<()>;
+  async fn repo_topic(&self, chat_id: &str, repo: &str) -> Result<Option<i64>>;
+  async fn set_repo_topic(
+    &self,
+    chat_id: &str,
+    repo: &str,
+    topic_id: i64,
+  ) -> Result<()>;
+  async fn upsert_retry(&self, item: &RetryItem) -> Result<()>;
+  async fn due_retries(&self, now: i64) -> Result<Vec<RetryItem>>;
+  async fn remove_retry(&self, id: &str) -> Result<()>;
+  async fn prune(&self, older_than: DateTime<Utc>) -> Result<u64>;
+  async fn sent_message(&self, thread_id: &str) -> Result<Option<(String, i64)>>;
+  async fn set_sent_message(
+    &self,
+    thread_id: &str,
+    chat_id: &str,
+    message_id: i64,
+  ) -> Result<()>;
+}
+
+// Chat id recorded for sends that aren't routed, including every row written
+// before dedupe became per chat.
+pub const UNROUTED_CHAT: &str = "";
+
+#[derive(Clone)]
+pub struct StoreOptions {
+  pub database_url: String,
+  pub statement_timeout: Option<Duration>,
+  pub require_tls: bool,
+  pub ca_cert: Option<PathBuf>,
+  pub max_retries: u32,
+  pub max_connections: u32,
+  pub connect_timeout: Duration,
+}
+
+impl StoreOptions {
+  // The url's scheme, which is all that gets logged of it: the rest can carry
+  // a password.
+  pub fn backend(&self) -> &str {
+    let url = self.database_url.as_str();
+    url.split(':').next().unwrap_or(url)
+  }
+}
+
+impl fmt::Debug for StoreOptions {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("StoreOptions")
+      .field("backend", &self.backend())
+      .field("statement_timeout", &self.statement_timeout)
+      .field("require_tls", &self.require_tls)
+      .field("ca_cert", &self.ca_cert)
+      .field("max_retries", &self.max_retries)
+      .field("max_connections", &self.max_connections)
+      .field("connect_timeout", &self.connect_timeout)
+      .finish()
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PollStats {
+  pub fetched: i64,
+  pub forwarded: i64,
+  pub filtered: i64,
+  pub failed: i64,
+  // The same counts per (repo, reason). Grouped digest flushes only count
+  // toward the totals, since a digest spans several repos.
+  pub sources: BTreeMap<(String, String), PollCounts>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PollCounts {
+  pub fetched: i64,
+  pub forwarded: i64,
+  pub filtered: i64,
+  pub failed: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsOutcome {
+  Fetched,
+  Forwarded,
+  Filtered,
+  Failed,
+}
+
+impl PollStats {
+  pub fn count(&mut self, repo: &str, reason: &str, outcome: StatsOutcome) {
+    let source = self
+      .sources
+      .entry((repo.to_string(), reason.to_string()))
+      .or_default();
+    let (total, per_source) = match outcome {
+      StatsOutcome::Fetched => (&mut self.fetched, &mut source.fetched),
+      StatsOutcome::Forwarded => (&mut self.forwarded, &mut source.forwarded),
+      StatsOutcome::Filtered => (&mut self.filtered, &mut source.filtered),
+      StatsOutcome::Failed => (&mut self.failed, &mut source.failed),
+    };
+    *total += 1;
+    *per_source += 1;
+  }
+}
+
+impl PollCounts {
+  pub fn add(&mut self, other: &PollCounts) {
+    self.fetched += other.fetched;
+    self.forwarded += other.forwarded;
+    self.filtered += other.filtered;
+    self.failed += other.failed;
+  }
+}
+
+type SourceRow = (String, String, i64, i64, i64, i64);
+
+fn sources_from_rows(rows: Vec<SourceRow>) -> BTreeMap<(String, String), PollCounts> {
+  rows
+    .into_iter()
+    .map(|(repo, reason, fetched, forwarded, filtered, failed)| {
+      let counts = PollCounts {
+        fetched,
+        forwarded,
+        filtered,
+        failed,
+      };
+      ((repo, reason), counts)
+    })
+    .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct PendingNotification {
+  pub id: String,
+  pub payload: String,
+  pub html_url: Option<String>,
+  // The route it was held for; None is TELEGRAM_CHAT_ID.
+  pub chat_id: Option<String>,
+  pub held_at: i64,
+}
+
+pub struct Escalation {
+  pub account: String,
+  pub thread_id: String,
+  pub payload: String,
+  pub html_url: Option<String>,
+  pub first_sent_at: i64,
+  pub level: i64,
+  pub next_at: i64,
+}
+
+// A failed send waiting in the retry queue. `dead` marks items that ran out
+// of attempts; they stay in the table for inspection but are never retried.
+pub struct RetryItem {
+  pub id: String,
+  pub payload: String,
+  pub html_url: Option<String>,
+  pub chat_id: Option<String>,
+  pub topic_id: Option<i64>,
+  pub attempts: i64,
+  pub next_at: i64,
+  pub dead: bool,
+}
+
+pub struct FeedEntry {
+  pub id: String,
+  pub repo: String,
+  pub title: String,
+  pub html_url: Option<String>,
+  pub forwarded_at: i64,
+}
+
+pub const AUDIT_SENT: &str = "sent";
+pub const AUDIT_ALREADY_SENT: &str = "skipped-already-sent";
+pub const AUDIT_FILTERED: &str = "skipped-filtered";
+pub const AUDIT_FAILED: &str = "failed";
+
+pub struct AuditEntry {
+  pub id: String,
+  pub decision: String,
+  pub detail: String,
+  pub recorded_at: i64,
+}
+
+type AuditRow = (String, String, String, i64);
+
+fn audit_entry_from_row(row: AuditRow) -> AuditEntry {
+  let (id, decision, detail, recorded_at) = row;
+  AuditEntry {
+    id,
+    decision,
+    detail,
+    recorded_at,
+  }
+}
+
+type RetryRow = (
+  String,
+  String,
+  Option<String>,
+  Option<String>,
+  Option<i64>,
+  i64,
+  i64,
+  bool,
+);
+
+fn retry_item_from_row(row: RetryRow) -> RetryItem {
+  let (id, payload, html_url, chat_id, topic_id, attempts, next_at, dead) = row;
+  RetryItem {
+    id,
+    payload,
+    html_url,
+    chat_id,
+    topic_id,
+    attempts,
+    next_at,
+    dead,
+  }
+}
+
+type FeedEntryRow = (String, String, String, Option<String>, i64);
+
+fn feed_entry_from_row(row: FeedEntryRow) -> FeedEntry {
+  let (id, repo, title, html_url, forwarded_at) = row;
+  FeedEntry {
+    id,
+    repo,
+    title,
+    html_url,
+    forwarded_at,
+  }
+}
+
+type EscalationRow = (String, String, String, Option<String>, i64, i64, i64);
+
+fn escalation_from_row(row: EscalationRow) -> Escalation {
+  let (account, thread_id, payload, html_url, first_sent_at, level, next_at) = row;
+  Escalation {
+    account,
+    thread_id,
+    payload,
+    html_url,
+    first_sent_at,
+    level,
+    next_at,
+  }
+}
+
+pub const META_NOTIFICATIONS_ETAG: &str = "notifications_etag";
+pub const META_GITHUB_POLL_INTERVAL: &str = "github_poll_interval";
+pub const META_DAEMON_VERSION: &str = "daemon_version";
+const META_WRITE_PROBE: &str = "write_probe";
+
+// `ETag` and `Last-Modified` of the first notifications page an account last
+// fetched, sent back as If-None-Match / If-Modified-Since.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageValidators {
+  pub etag: Option<String>,
+  pub last_modified: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PollMeta {
+  // Keyed by account label, stored as JSON under META_NOTIFICATIONS_ETAG.
+  pub notifications: HashMap<String, PageValidators>,
+  pub github_poll_interval: Option<u64>,
+}
+
+impl PollMeta {
+  pub async fn load(store: &dyn NotificationStore) -> Result<Self> {
+    // Older versions stored a bare etag here; it is dropped and the next poll
+    // goes out unconditionally.
+    let notifications = store
+      .get_meta(META_NOTIFICATIONS_ETAG)
+      .await?
+      .and_then(|raw| serde_json::from_str(&raw).ok())
+      .unwrap_or_default();
+    let github_poll_interval = store
+      .get_meta(META_GITHUB_POLL_INTERVAL)
+      .await?
+      .and_then(|raw| raw.parse().ok());
+
+    Ok(Self {
+      notifications,
+      github_poll_interval,
+    })
+  }
+
+  pub async fn save(&self, store: &dyn NotificationStore) -> Result<()> {
+    let notifications = serde_json::to_string(&self.notifications)
+      .context("encode notification validators")?;
+    store
+      .set_meta(META_NOTIFICATIONS_ETAG, &notifications)
+      .await?;
+    if let Some(interval) = self.github_poll_interval {
+      store
+        .set_meta(META_GITHUB_POLL_INTERVAL, &interval.to_string())
+        .await?;
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PollStatsSummary {
+  pub polls: i64,
+  pub totals: PollStats,
+}
+
+// Rows in sent_notifications, one per notification and chat it went to.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SentStats {
+  pub total: i64,
+  pub since: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DbOutagePolicy {
+  Halt,
+  ForwardWithoutDedupe,
+}
+
+impl DbOutagePolicy {
+  pub fn parse(raw: &str) -> Result<Self> {
+    match raw.to_ascii_lowercase().as_str() {
+      "halt" => Ok(Self::Halt),
+      "forward_without_dedupe" => Ok(Self::ForwardWithoutDedupe),
+      _ => bail!("invalid DB_OUTAGE_POLICY: {raw}, use halt or forward_without_dedupe"),
+    }
+  }
+}
+
+// Dedupe keys, chats and updated_at forwarded while the store was
+// unreachable, written once it recovers.
+#[derive(Debug, Clone, Default)]
+pub struct DedupeBackfill(Arc<Mutex<Vec<BackfillMark>>>);
+
+type BackfillMark = (String, String, DateTime<Utc>);
+
+impl DedupeBackfill {
+  pub fn push(&self, key: &str, chat_id: &str, updated_at: DateTime<Utc>) {
+    if let Ok(mut keys) = self.0.lock() {
+      keys.push((key.to_string(), chat_id.to_string(), updated_at));
+    }
+  }
+
+  // Stops at the first failure, keeping that key and the rest for next time.
+  pub async fn flush(&self, store: &dyn NotificationStore) -> Result<usize> {
+    let keys = match self.0.lock() {
+      Ok(mut keys) => std::mem::take(&mut *keys),
+      Err(_) => return Ok(0),
+    };
+    for (idx, (key, chat_id, updated_at)) in keys.iter().enumerate() {
+      if let Err(err) = store.mark_sent(key, chat_id, *updated_at).await {
+        if let Ok(mut pending) = self.0.lock() {
+          pending.splice(0..0, keys[idx..].iter().cloned());
+        }
+        return Err(err);
+      }
+    }
+    Ok(keys.len())
+  }
+}
+
+// Rows written before dedupe tracked updated_at carry it as a `:<epoch>`
+// suffix on the key, or not at all. Plain keys count as sent up to `now`,
+// which is what id-only dedupe suppressed anyway. Duplicates keep the latest.
+fn legacy_sent_rows(
+  rows: Vec<(String, String)>,
+  now: i64,
+) -> HashMap<(String, String), i64> {
+  let mut upgraded = HashMap::new();
+  for (key, chat_id) in rows {
+    let (id, updated_at) = split_legacy_key(&key).unwrap_or((&key, now));
+    let latest = upgraded
+      .entry((id.to_string(), chat_id))
+      .or_insert(updated_at);
+    *latest = (*latest).max(updated_at);
+  }
+  upgraded
+}
+
+// `<thread id>:<epoch>`, optionally behind an account label. Anything else,
+// like `label:<thread id>` or `event:<id>`, is a plain key.
+fn split_legacy_key(key: &str) -> Option<(&str, i64)> {
+  let (id, updated_at) = key.rsplit_once(':')?;
+  let thread = id.rsplit(':').next().unwrap_or(id);
+  let numeric = |raw: &str| !raw.is_empty() && raw.bytes().all(|b| b.is_ascii_digit());
+  if !numeric(thread) || !numeric(updated_at) {
+    return None;
+  }
+  Some((id, updated_at.parse().ok()?))
 }
 
 pub struct SqliteStore {
   pool: SqlitePool,
+  limits: QueryLimits,
 }
 
 pub struct PostgresStore {
   pool: PgPool,
+  limits: QueryLimits,
+}
+
+// Bounds applied to every store query. The timeout covers each attempt end to
+// end; sqlite's busy timeout alone only bounds the wait for a lock.
+#[derive(Debug, Clone, Copy)]
+struct QueryLimits {
+  max_retries: u32,
+  timeout: Option<Duration>,
+}
+
+impl QueryLimits {
+  fn new(options: &StoreOptions) -> Self {
+    Self {
+      max_retries: options.max_retries,
+      timeout: options.statement_timeout,
+    }
+  }
 }
 
-pub async fn connect_store(database_url: &str) -> Result<Box<dyn NotificationStore>> {
+// DB_REQUIRE_TLS verifies both the certificate chain and the host name.
+fn postgres_connect_options(options: &StoreOptions) -> Result<PgConnectOptions> {
+  let database_url = options.database_url.as_str();
+  let mut connect_options = PgConnectOptions::from_str(database_url)
+    .with_context(|| format!("parse postgres database url: {database_url}"))?;
+  if let Some(timeout) = options.statement_timeout {
+    connect_options =
+      connect_options.options([("statement_timeout", timeout.as_millis().to_string())]);
+  }
+  if options.require_tls {
+    connect_options = connect_options.ssl_mode(PgSslMode::VerifyFull);
+  }
+  if let Some(ca_cert) = &options.ca_cert {
+    connect_options = connect_options.ssl_root_cert(ca_cert);
+  }
+  Ok(connect_options)
+}
+
+pub async fn connect_store(
+  options: &StoreOptions,
+) -> Result<Box<dyn NotificationStore>> {
+  let database_url = options.database_url.as_str();
+  let statement_timeout = options.statement_timeout;
+
   if database_url.starts_with("postgres://")
     || database_url.starts_with("postgresql://")
   {
     let pool = PgPoolOptions::new()
-      .max_connections(5)
-      .connect(database_url)
+      .max_connections(options.max_connections)
+      .acquire_timeout(options.connect_timeout)
+      .connect_with(postgres_connect_options(options)?)
       .await
-      .with_context(|| format!("connect postgres database: {database_url}"))?;
-    return Ok(Box::new(PostgresStore { pool }) as Box<dyn NotificationStore>);
+      .with_context(|| {
+        if options.require_tls {
+          format!(
+            "connect postgres database with verified TLS required: {database_url}"
+          )
+        } else {
+          format!("connect postgres database: {database_url}")
+        }
+      })?;
+    return Ok(Box::new(PostgresStore {
+      pool,
+      limits: QueryLimits::new(options),
+    }) as Box<dyn NotificationStore>);
+  }
+
+  if database_url.starts_with("mysql://") {
+    #[cfg(feature = "mysql")]
+    return mysql::connect(options).await;
+    #[cfg(not(feature = "mysql"))]
+    bail!("mysql:// needs a build with the mysql cargo feature enabled");
+  }
+
+  if database_url == "memory://" {
+    return Ok(Box::new(memory::MemoryStore::default()) as Box<dyn NotificationStore>);
   }
 
   if database_url.starts_with("sqlite://") {
     ensure_sqlite_parent_dir(database_url)?;
+    let mut connect_options = SqliteConnectOptions::from_str(database_url)
+      .with_context(|| format!("parse sqlite database url: {database_url}"))?;
+    if let Some(timeout) = statement_timeout {
+      connect_options = connect_options.busy_timeout(timeout);
+    }
     let pool = SqlitePoolOptions::new()
-      .max_connections(5)
-      .connect(database_url)
+      .max_connections(options.max_connections)
+      .acquire_timeout(options.connect_timeout)
+      .connect_with(connect_options)
       .await
       .with_context(|| format!("connect sqlite database: {database_url}"))?;
-    return Ok(Box::new(SqliteStore { pool }) as Box<dyn NotificationStore>);
+    return Ok(Box::new(SqliteStore {
+      pool,
+      limits: QueryLimits::new(options),
+    }) as Box<dyn NotificationStore>);
   }
 
-  bail!("unsupported DATABASE_URL scheme, use sqlite:// or postgres://")
+  bail!("unsupported DATABASE_URL scheme, use sqlite://, postgres://, mysql:// or memory://")
 }
 
-#[async_trait]
-impl NotificationStore for SqliteStore {
-  async fn init(&self) -> Result<()> {
-    sqlx::query(
-      "CREATE TABLE IF NOT EXISTS sent_notifications (
-                id TEXT PRIMARY KEY,
-                sent_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+// Applied in order; the position in the list is the schema version. Only
+// ever append, since databases record how far they got.
+const SQLITE_MIGRATIONS: &[&str] = &[
+  "CREATE TABLE IF NOT EXISTS sent_notifications (
+    id TEXT NOT NULL,
+    chat_id TEXT NOT NULL DEFAULT '',
+    sent_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id, chat_id)
+  )",
+  "CREATE TABLE IF NOT EXISTS poll_stats (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    polled_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    fetched INTEGER NOT NULL,
+    forwarded INTEGER NOT NULL,
+    filtered INTEGER NOT NULL,
+    failed INTEGER NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS pending_group (
+    id TEXT PRIMARY KEY,
+    payload TEXT NOT NULL,
+    html_url TEXT,
+    held_at INTEGER NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS escalations (
+    account TEXT NOT NULL DEFAULT '',
+    thread_id TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    html_url TEXT,
+    first_sent_at INTEGER NOT NULL,
+    level INTEGER NOT NULL,
+    next_at INTEGER NOT NULL,
+    PRIMARY KEY (account, thread_id)
+  )",
+  "CREATE TABLE IF NOT EXISTS feed_entries (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    id TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    title TEXT NOT NULL,
+    html_url TEXT,
+    forwarded_at INTEGER NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS muted_repos (
+    repo TEXT PRIMARY KEY,
+    until INTEGER NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS thread_read_state (
+    thread_id TEXT PRIMARY KEY,
+    unread INTEGER NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS audit_log (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    id TEXT NOT NULL,
+    decision TEXT NOT NULL,
+    detail TEXT NOT NULL,
+    recorded_at INTEGER NOT NULL
+  )",
+  // One row per account label; a single account uses the empty label.
+  "CREATE TABLE IF NOT EXISTS poll_state (
+    account TEXT PRIMARY KEY,
+    since_cursor INTEGER NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS repo_topics (
+    chat_id TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    topic_id INTEGER NOT NULL,
+    PRIMARY KEY (chat_id, repo)
+  )",
+  "CREATE TABLE IF NOT EXISTS retry_queue (
+    id TEXT PRIMARY KEY,
+    payload TEXT NOT NULL,
+    html_url TEXT,
+    chat_id TEXT,
+    topic_id INTEGER,
+    attempts INTEGER NOT NULL,
+    next_at INTEGER NOT NULL,
+    dead INTEGER NOT NULL
+  )",
+  // The Telegram message last sent for a thread, edited by EDIT_ON_UPDATE.
+  "CREATE TABLE IF NOT EXISTS sent_messages (
+    thread_id TEXT PRIMARY KEY,
+    chat_id TEXT NOT NULL,
+    message_id INTEGER NOT NULL
+  )",
+  // The route a held notification is flushed to; NULL for TELEGRAM_CHAT_ID.
+  "ALTER TABLE pending_group ADD COLUMN chat_id TEXT",
+  // Epoch seconds of the latest updated_at sent; filled in for older rows by
+  // upgrade_sent_notifications.
+  "ALTER TABLE sent_notifications ADD COLUMN updated_at INTEGER",
+  // poll_stats broken down by the repo and reason of each notification.
+  "CREATE TABLE IF NOT EXISTS poll_source_stats (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    polled_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    repo TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    fetched INTEGER NOT NULL,
+    forwarded INTEGER NOT NULL,
+    filtered INTEGER NOT NULL,
+    failed INTEGER NOT NULL
+  )",
+];
+
+impl SqliteStore {
+  // Runs the migrations past the recorded schema_version, each in a
+  // transaction with its version row. The statements are idempotent, so
+  // databases created before schema_version existed start again from 1.
+  async fn migrate(&self) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+                version INTEGER PRIMARY KEY,
+                applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
             )",
+      )
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("create schema_version table in sqlite")?;
+
+    let current = retry_db(self.limits, || {
+      sqlx::query_scalar::<_, i64>(
+        "SELECT CAST(COALESCE(MAX(version), 0) AS BIGINT) FROM schema_version",
+      )
+      .fetch_one(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("read schema version in sqlite")?;
+    let latest = SQLITE_MIGRATIONS.len() as i64;
+    if current > latest {
+      bail!(
+        "sqlite schema version {current} is newer than this build supports ({latest}), \
+         refusing to start"
+      );
+    }
+
+    for (version, statement) in (1..).zip(SQLITE_MIGRATIONS).skip(current as usize) {
+      let mut tx = self.pool.begin().await.context("begin sqlite migration")?;
+      sqlx::query(statement)
+        .execute(&mut *tx)
+        .await
+        .map_err(describe_timeout)
+        .with_context(|| format!("apply sqlite migration {version}"))?;
+      sqlx::query("INSERT INTO schema_version (version) VALUES (?)")
+        .bind(version)
+        .execute(&mut *tx)
+        .await
+        .map_err(describe_timeout)
+        .with_context(|| format!("record sqlite schema version {version}"))?;
+      tx.commit().await.context("commit sqlite migration")?;
+      info!(version, "applied sqlite migration");
+    }
+
+    Ok(())
+  }
+
+  // Tables created before per-chat dedupe are keyed by id alone. SQLite can't
+  // change a primary key in place, so the table is rebuilt once.
+  async fn upgrade_sent_notifications(&self) -> Result<()> {
+    let has_chat_id = sqlx::query_scalar::<_, i64>(
+      "SELECT COUNT(*) FROM pragma_table_info('sent_notifications') WHERE name = 'chat_id'",
+    )
+    .fetch_one(&self.pool)
+    .await
+    .map_err(describe_timeout)
+    .context("inspect sent_notifications table in sqlite")?;
+    if has_chat_id == 0 {
+      let mut tx = self.pool.begin().await.context("begin sqlite upgrade")?;
+      for statement in [
+        "CREATE TABLE sent_notifications_new (
+                id TEXT NOT NULL,
+                chat_id TEXT NOT NULL DEFAULT '',
+                sent_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at INTEGER,
+                PRIMARY KEY (id, chat_id)
+            )",
+        "INSERT INTO sent_notifications_new (id, sent_at)
+         SELECT id, sent_at FROM sent_notifications",
+        "DROP TABLE sent_notifications",
+        "ALTER TABLE sent_notifications_new RENAME TO sent_notifications",
+      ] {
+        sqlx::query(statement)
+          .execute(&mut *tx)
+          .await
+          .map_err(describe_timeout)
+          .context("add chat_id to sent_notifications in sqlite")?;
+      }
+      tx.commit().await.context("commit sqlite upgrade")?;
+    }
+
+    self.backfill_updated_at().await
+  }
+
+  // Escalations were keyed by thread id alone before multi-account polling;
+  // rows from then belong to the single account, whose label is empty.
+  async fn upgrade_escalations(&self) -> Result<()> {
+    let has_account = sqlx::query_scalar::<_, i64>(
+      "SELECT COUNT(*) FROM pragma_table_info('escalations') WHERE name = 'account'",
     )
-    .execute(&self.pool)
+    .fetch_one(&self.pool)
     .await
-    .context("create sent_notifications table in sqlite")?;
+    .map_err(describe_timeout)
+    .context("inspect escalations table in sqlite")?;
+    if has_account > 0 {
+      return Ok(());
+    }
+
+    let mut tx = self.pool.begin().await.context("begin sqlite upgrade")?;
+    for statement in [
+      "CREATE TABLE escalations_new (
+                account TEXT NOT NULL DEFAULT '',
+                thread_id TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                html_url TEXT,
+                first_sent_at INTEGER NOT NULL,
+                level INTEGER NOT NULL,
+                next_at INTEGER NOT NULL,
+                PRIMARY KEY (account, thread_id)
+            )",
+      "INSERT INTO escalations_new
+           (thread_id, payload, html_url, first_sent_at, level, next_at)
+         SELECT thread_id, payload, html_url, first_sent_at, level, next_at
+           FROM escalations",
+      "DROP TABLE escalations",
+      "ALTER TABLE escalations_new RENAME TO escalations",
+    ] {
+      sqlx::query(statement)
+        .execute(&mut *tx)
+        .await
+        .map_err(describe_timeout)
+        .context("add account to escalations in sqlite")?;
+    }
+    tx.commit().await.context("commit sqlite upgrade")?;
 
     Ok(())
   }
 
-  async fn is_sent(&self, id: &str) -> Result<bool> {
-    let exists = sqlx::query_scalar::<_, i64>(
-      "SELECT 1 FROM sent_notifications WHERE id = ? LIMIT 1",
+  async fn backfill_updated_at(&self) -> Result<()> {
+    let mut tx = self.pool.begin().await.context("begin sqlite upgrade")?;
+    let rows = sqlx::query_as::<_, (String, String)>(
+      "SELECT id, chat_id FROM sent_notifications WHERE updated_at IS NULL",
     )
-    .bind(id)
-    .fetch_optional(&self.pool)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(describe_timeout)
+    .context("load sent notifications without updated_at in sqlite")?;
+    if rows.is_empty() {
+      return Ok(());
+    }
+
+    let upgraded = legacy_sent_rows(rows, Utc::now().timestamp());
+    sqlx::query("DELETE FROM sent_notifications WHERE updated_at IS NULL")
+      .execute(&mut *tx)
+      .await
+      .map_err(describe_timeout)
+      .context("drop legacy sent notifications in sqlite")?;
+    for ((id, chat_id), updated_at) in &upgraded {
+      sqlx::query(
+        "INSERT INTO sent_notifications (id, chat_id, updated_at) VALUES (?, ?, ?)
+         ON CONFLICT (id, chat_id) DO UPDATE
+           SET updated_at = MAX(sent_notifications.updated_at, excluded.updated_at)",
+      )
+      .bind(id)
+      .bind(chat_id)
+      .bind(updated_at)
+      .execute(&mut *tx)
+      .await
+      .map_err(describe_timeout)
+      .context("add updated_at to sent_notifications in sqlite")?;
+    }
+    tx.commit().await.context("commit sqlite upgrade")?;
+    info!(
+      rows = upgraded.len(),
+      "added updated_at to sent notifications"
+    );
+
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl NotificationStore for SqliteStore {
+  async fn init(&self) -> Result<()> {
+    self.migrate().await?;
+    self.upgrade_sent_notifications().await?;
+    self.upgrade_escalations().await?;
+
+    // A read-only database would fail every mark_sent after a successful send
+    // and re-send the same notifications forever, so refuse to start instead.
+    let now = Utc::now().timestamp().to_string();
+    self.set_meta(META_WRITE_PROBE, &now).await.context(
+      "database is not writable (read-only file or mount?), refusing to start",
+    )?;
+
+    Ok(())
+  }
+
+  async fn is_sent(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<bool> {
+    let exists = retry_db(self.limits, || {
+      sqlx::query_scalar::<_, i64>(
+        "SELECT 1 FROM sent_notifications
+         WHERE id = ? AND chat_id = ? AND updated_at >= ? LIMIT 1",
+      )
+      .bind(id)
+      .bind(chat_id)
+      .bind(updated_at.timestamp())
+      .fetch_optional(&self.pool)
+    })
     .await
+    .map_err(describe_timeout)
     .context("run sqlite dedupe query")?
     .is_some();
 
     Ok(exists)
   }
 
-  async fn mark_sent(&self, id: &str) -> Result<()> {
-    sqlx::query("INSERT OR IGNORE INTO sent_notifications (id) VALUES (?)")
+  async fn mark_sent(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(SQLITE_MARK_SENT)
+        .bind(id)
+        .bind(chat_id)
+        .bind(updated_at.timestamp())
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("mark notification as sent in sqlite: {id}"))?;
+
+    Ok(())
+  }
+
+  async fn try_claim(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<bool> {
+    let result = retry_db(self.limits, || {
+      sqlx::query(SQLITE_MARK_SENT)
+        .bind(id)
+        .bind(chat_id)
+        .bind(updated_at.timestamp())
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("claim notification in sqlite: {id}"))?;
+
+    Ok(result.rows_affected() == 1)
+  }
+
+  // Only undoes this claim; a newer one from another instance stays.
+  async fn release_claim(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "DELETE FROM sent_notifications WHERE id = ? AND chat_id = ? AND updated_at = ?",
+      )
       .bind(id)
+      .bind(chat_id)
+      .bind(updated_at.timestamp())
       .execute(&self.pool)
-      .await
-      .with_context(|| format!("mark notification as sent in sqlite: {id}"))?;
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("release notification claim in sqlite: {id}"))?;
 
     Ok(())
   }
-}
 
-#[async_trait]
-impl NotificationStore for PostgresStore {
-  async fn init(&self) -> Result<()> {
-    sqlx::query(
-      "CREATE TABLE IF NOT EXISTS sent_notifications (
-                id TEXT PRIMARY KEY,
-                sent_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
-            )",
-    )
-    .execute(&self.pool)
+  async fn record_poll_stats(&self, stats: &PollStats) -> Result<()> {
+    retry_db(self.limits, || async {
+      let mut tx = self.pool.begin().await?;
+      sqlx::query(
+        "INSERT INTO poll_stats (fetched, forwarded, filtered, failed) VALUES (?, ?, ?, ?)",
+      )
+      .bind(stats.fetched)
+      .bind(stats.forwarded)
+      .bind(stats.filtered)
+      .bind(stats.failed)
+      .execute(&mut *tx)
+      .await?;
+      for ((repo, reason), counts) in &stats.sources {
+        sqlx::query(
+          "INSERT INTO poll_source_stats
+             (repo, reason, fetched, forwarded, filtered, failed)
+           VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(repo)
+        .bind(reason)
+        .bind(counts.fetched)
+        .bind(counts.forwarded)
+        .bind(counts.filtered)
+        .bind(counts.failed)
+        .execute(&mut *tx)
+        .await?;
+      }
+      tx.commit().await
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("record poll stats in sqlite")?;
+
+    Ok(())
+  }
+
+  async fn poll_stats_since(&self, since: DateTime<Utc>) -> Result<PollStatsSummary> {
+    // sent_at/polled_at use sqlite's CURRENT_TIMESTAMP text format.
+    let (polls, fetched, forwarded, filtered, failed) = retry_db(self.limits, || {
+      sqlx::query_as::<_, (i64, i64, i64, i64, i64)>(
+        "SELECT COUNT(*), COALESCE(SUM(fetched), 0), COALESCE(SUM(forwarded), 0),
+                COALESCE(SUM(filtered), 0), COALESCE(SUM(failed), 0)
+           FROM poll_stats WHERE polled_at >= ?",
+      )
+      .bind(sqlite_timestamp(since))
+      .fetch_one(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("aggregate poll stats in sqlite")?;
+    let sources = retry_db(self.limits, || {
+      sqlx::query_as::<_, SourceRow>(
+        "SELECT repo, reason, SUM(fetched), SUM(forwarded), SUM(filtered), SUM(failed)
+           FROM poll_source_stats WHERE polled_at >= ? GROUP BY repo, reason",
+      )
+      .bind(sqlite_timestamp(since))
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("aggregate poll stats per repo and reason in sqlite")?;
+
+    Ok(PollStatsSummary {
+      polls,
+      totals: PollStats {
+        fetched,
+        forwarded,
+        filtered,
+        failed,
+        sources: sources_from_rows(sources),
+      },
+    })
+  }
+
+  async fn hold_pending(
+    &self,
+    id: &str,
+    payload: &str,
+    html_url: Option<&str>,
+    chat_id: Option<&str>,
+  ) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO pending_group (id, payload, html_url, chat_id, held_at)
+       VALUES (?, ?, ?, ?, ?)
+       ON CONFLICT (id) DO UPDATE SET payload = excluded.payload,
+         html_url = excluded.html_url, chat_id = excluded.chat_id",
+      )
+      .bind(id)
+      .bind(payload)
+      .bind(html_url)
+      .bind(chat_id)
+      .bind(Utc::now().timestamp())
+      .execute(&self.pool)
+    })
     .await
-    .context("create sent_notifications table in postgres")?;
+    .map_err(describe_timeout)
+    .with_context(|| format!("hold pending notification in sqlite: {id}"))?;
 
     Ok(())
   }
 
-  async fn is_sent(&self, id: &str) -> Result<bool> {
-    let exists = sqlx::query_scalar::<_, i64>(
-      "SELECT 1 FROM sent_notifications WHERE id = $1 LIMIT 1",
+  async fn list_pending(&self) -> Result<Vec<PendingNotification>> {
+    let rows = retry_db(self.limits, || {
+      sqlx::query_as::<_, (String, String, Option<String>, Option<String>, i64)>(
+        "SELECT id, payload, html_url, chat_id, held_at
+         FROM pending_group ORDER BY held_at, id",
+      )
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list pending notifications in sqlite")?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(
+          |(id, payload, html_url, chat_id, held_at)| PendingNotification {
+            id,
+            payload,
+            html_url,
+            chat_id,
+            held_at,
+          },
+        )
+        .collect(),
     )
-    .bind(id)
-    .fetch_optional(&self.pool)
+  }
+
+  async fn remove_pending(&self, id: &str) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM pending_group WHERE id = ?")
+        .bind(id)
+        .execute(&self.pool)
+    })
     .await
-    .context("run postgres dedupe query")?
-    .is_some();
+    .map_err(describe_timeout)
+    .with_context(|| format!("remove pending notification in sqlite: {id}"))?;
 
-    Ok(exists)
+    Ok(())
   }
 
-  async fn mark_sent(&self, id: &str) -> Result<()> {
-    sqlx::query(
-      "INSERT INTO sent_notifications (id) VALUES ($1) ON CONFLICT (id) DO NOTHING",
+  async fn get_meta(&self, key: &str) -> Result<Option<String>> {
+    retry_db(self.limits, || {
+      sqlx::query_scalar::<_, String>("SELECT value FROM meta WHERE key = ?")
+        .bind(key)
+        .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("read meta key in sqlite: {key}"))
+  }
+
+  async fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO meta (key, value) VALUES (?, ?)
+         ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+      )
+      .bind(key)
+      .bind(value)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("write meta key in sqlite: {key}"))?;
+
+    Ok(())
+  }
+
+  async fn upsert_escalation(&self, escalation: &Escalation) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO escalations
+           (account, thread_id, payload, html_url, first_sent_at, level, next_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT (account, thread_id) DO UPDATE SET
+           payload = excluded.payload,
+           html_url = excluded.html_url,
+           first_sent_at = excluded.first_sent_at,
+           level = excluded.level,
+           next_at = excluded.next_at",
+      )
+      .bind(&escalation.account)
+      .bind(&escalation.thread_id)
+      .bind(&escalation.payload)
+      .bind(&escalation.html_url)
+      .bind(escalation.first_sent_at)
+      .bind(escalation.level)
+      .bind(escalation.next_at)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| {
+      format!("upsert escalation in sqlite: {}", escalation.thread_id)
+    })?;
+
+    Ok(())
+  }
+
+  async fn due_escalations(&self, now: i64) -> Result<Vec<Escalation>> {
+    let rows = retry_db(self.limits, || {
+      sqlx::query_as::<_, EscalationRow>(
+        "SELECT account, thread_id, payload, html_url, first_sent_at, level, next_at
+         FROM escalations WHERE next_at <= ? ORDER BY next_at",
+      )
+      .bind(now)
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list due escalations in sqlite")?;
+
+    Ok(rows.into_iter().map(escalation_from_row).collect())
+  }
+
+  async fn remove_escalation(&self, account: &str, thread_id: &str) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM escalations WHERE account = ? AND thread_id = ?")
+        .bind(account)
+        .bind(thread_id)
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("remove escalation in sqlite: {thread_id}"))?;
+
+    Ok(())
+  }
+
+  async fn record_feed_entry(&self, entry: &FeedEntry, keep: i64) -> Result<()> {
+    retry_db(self.limits, || sqlx::query(
+      "INSERT INTO feed_entries (id, repo, title, html_url, forwarded_at) VALUES (?, ?, ?, ?, ?)",
     )
-    .bind(id)
-    .execute(&self.pool)
+    .bind(&entry.id)
+    .bind(&entry.repo)
+    .bind(&entry.title)
+    .bind(&entry.html_url)
+    .bind(entry.forwarded_at)
+    .execute(&self.pool))
     .await
-    .with_context(|| format!("mark notification as sent in postgres: {id}"))?;
+    .map_err(describe_timeout)
+    .with_context(|| format!("record feed entry in sqlite: {}", entry.id))?;
+
+    retry_db(self.limits, || {
+      sqlx::query(
+        "DELETE FROM feed_entries
+         WHERE seq NOT IN (SELECT seq FROM feed_entries ORDER BY seq DESC LIMIT ?)",
+      )
+      .bind(keep)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("prune feed entries in sqlite")?;
 
     Ok(())
   }
-}
 
-fn ensure_sqlite_parent_dir(database_url: &str) -> Result<()> {
-  if database_url == "sqlite::memory:" {
-    return Ok(());
+  async fn recent_feed_entries(&self, limit: i64) -> Result<Vec<FeedEntry>> {
+    let rows = retry_db(self.limits, || {
+      sqlx::query_as::<_, FeedEntryRow>(
+        "SELECT id, repo, title, html_url, forwarded_at
+         FROM feed_entries ORDER BY seq DESC LIMIT ?",
+      )
+      .bind(limit)
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list feed entries in sqlite")?;
+
+    Ok(rows.into_iter().map(feed_entry_from_row).collect())
   }
 
-  let raw_path = database_url.trim_start_matches("sqlite://");
-  if raw_path.is_empty() {
-    return Ok(());
+  // Clears dedupe state plus the etag, cursors and cooldowns tied to it, so
+  // the next poll forwards everything again. Returns the number of sent rows
+  // removed.
+  async fn reset(&self) -> Result<u64> {
+    let removed = retry_db(self.limits, || {
+      sqlx::query("DELETE FROM sent_notifications").execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("clear sent notifications in sqlite")?
+    .rows_affected();
+
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM meta WHERE key = ? OR key LIKE 'cooldown:%'")
+        .bind(META_NOTIFICATIONS_ETAG)
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("clear dedupe meta in sqlite")?;
+
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM poll_state").execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("clear poll cursors in sqlite")?;
+
+    Ok(removed)
   }
 
-  let path = Path::new(raw_path);
-  if let Some(parent) = path.parent() {
-    if !parent.as_os_str().is_empty() {
-      std::fs::create_dir_all(parent)
-        .with_context(|| format!("create sqlite dir {}", parent.display()))?;
-    }
+  async fn mute_repo(&self, repo: &str, until: i64) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO muted_repos (repo, until) VALUES (?, ?)
+         ON CONFLICT (repo) DO UPDATE SET until = excluded.until",
+      )
+      .bind(repo)
+      .bind(until)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("mute repo in sqlite: {repo}"))?;
+
+    Ok(())
   }
 
-  Ok(())
+  // Expired mutes are dropped on read.
+  async fn active_mutes(&self, now: i64) -> Result<Vec<(String, i64)>> {
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM muted_repos WHERE until <= ?")
+        .bind(now)
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("clear expired mutes in sqlite")?;
+
+    retry_db(self.limits, || {
+      sqlx::query_as::<_, (String, i64)>("SELECT repo, until FROM muted_repos")
+        .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list muted repos in sqlite")
+  }
+
+  async fn read_state(&self, thread_id: &str) -> Result<Option<bool>> {
+    retry_db(self.limits, || {
+      sqlx::query_scalar::<_, bool>(
+        "SELECT unread FROM thread_read_state WHERE thread_id = ?",
+      )
+      .bind(thread_id)
+      .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("load read state in sqlite: {thread_id}"))
+  }
+
+  async fn set_read_state(&self, thread_id: &str, unread: bool) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO thread_read_state (thread_id, unread) VALUES (?, ?)
+         ON CONFLICT (thread_id) DO UPDATE SET unread = excluded.unread",
+      )
+      .bind(thread_id)
+      .bind(unread)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("store read state in sqlite: {thread_id}"))?;
+
+    Ok(())
+  }
+
+  async fn audit(&self, entry: &AuditEntry) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO audit_log (id, decision, detail, recorded_at)
+         VALUES (?, ?, ?, ?)",
+      )
+      .bind(&entry.id)
+      .bind(&entry.decision)
+      .bind(&entry.detail)
+      .bind(entry.recorded_at)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("record audit entry in sqlite: {}", entry.id))?;
+
+    Ok(())
+  }
+
+  async fn audit_since(&self, since: i64) -> Result<Vec<AuditEntry>> {
+    let rows = retry_db(self.limits, || {
+      sqlx::query_as::<_, AuditRow>(
+        "SELECT id, decision, detail, recorded_at
+         FROM audit_log WHERE recorded_at >= ? ORDER BY seq",
+      )
+      .bind(since)
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list audit entries in sqlite")?;
+
+    Ok(rows.into_iter().map(audit_entry_from_row).collect())
+  }
+
+  async fn load_cursor(&self, account: &str) -> Result<Option<DateTime<Utc>>> {
+    let cursor = retry_db(self.limits, || {
+      sqlx::query_scalar::<_, i64>(
+        "SELECT since_cursor FROM poll_state WHERE account = ?",
+      )
+      .bind(account)
+      .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("load poll cursor in sqlite")?;
+
+    Ok(cursor.and_then(|ts| DateTime::from_timestamp(ts, 0)))
+  }
+
+  async fn save_cursor(&self, account: &str, ts: DateTime<Utc>) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO poll_state (account, since_cursor) VALUES (?, ?)
+         ON CONFLICT (account) DO UPDATE SET since_cursor = excluded.since_cursor",
+      )
+      .bind(account)
+      .bind(ts.timestamp())
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("save poll cursor in sqlite")?;
+
+    Ok(())
+  }
+
+  async fn repo_topic(&self, chat_id: &str, repo: &str) -> Result<Option<i64>> {
+    retry_db(self.limits, || {
+      sqlx::query_scalar::<_, i64>(
+        "SELECT topic_id FROM repo_topics WHERE chat_id = ? AND repo = ?",
+      )
+      .bind(chat_id)
+      .bind(repo)
+      .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("load repo topic in sqlite: {repo}"))
+  }
+
+  async fn set_repo_topic(
+    &self,
+    chat_id: &str,
+    repo: &str,
+    topic_id: i64,
+  ) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO repo_topics (chat_id, repo, topic_id) VALUES (?, ?, ?)
+         ON CONFLICT (chat_id, repo) DO UPDATE SET topic_id = excluded.topic_id",
+      )
+      .bind(chat_id)
+      .bind(repo)
+      .bind(topic_id)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("store repo topic in sqlite: {repo}"))?;
+
+    Ok(())
+  }
+
+  async fn upsert_retry(&self, item: &RetryItem) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO retry_queue
+           (id, payload, html_url, chat_id, topic_id, attempts, next_at, dead)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT (id) DO UPDATE SET
+           payload = excluded.payload,
+           html_url = excluded.html_url,
+           chat_id = excluded.chat_id,
+           topic_id = excluded.topic_id,
+           attempts = excluded.attempts,
+           next_at = excluded.next_at,
+           dead = excluded.dead",
+      )
+      .bind(&item.id)
+      .bind(&item.payload)
+      .bind(&item.html_url)
+      .bind(&item.chat_id)
+      .bind(item.topic_id)
+      .bind(item.attempts)
+      .bind(item.next_at)
+      .bind(item.dead)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("upsert retry item in sqlite: {}", item.id))?;
+
+    Ok(())
+  }
+
+  async fn due_retries(&self, now: i64) -> Result<Vec<RetryItem>> {
+    let rows = retry_db(self.limits, || {
+      sqlx::query_as::<_, RetryRow>(
+        "SELECT id, payload, html_url, chat_id, topic_id, attempts, next_at, dead
+         FROM retry_queue WHERE NOT dead AND next_at <= ? ORDER BY next_at",
+      )
+      .bind(now)
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list due retries in sqlite")?;
+
+    Ok(rows.into_iter().map(retry_item_from_row).collect())
+  }
+
+  async fn remove_retry(&self, id: &str) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM retry_queue WHERE id = ?")
+        .bind(id)
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("remove retry item in sqlite: {id}"))?;
+
+    Ok(())
+  }
+
+  async fn prune(&self, older_than: DateTime<Utc>) -> Result<u64> {
+    let result = retry_db(self.limits, || {
+      sqlx::query("DELETE FROM sent_notifications WHERE sent_at < ?")
+        .bind(sqlite_timestamp(older_than))
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("prune sent notifications in sqlite")?;
+
+    Ok(result.rows_affected())
+  }
+
+  async fn sent_stats(&self, since: DateTime<Utc>) -> Result<SentStats> {
+    let (total, since) = retry_db(self.limits, || {
+      sqlx::query_as::<_, (i64, i64)>(
+        "SELECT COUNT(*), COALESCE(SUM(CASE WHEN sent_at >= ? THEN 1 ELSE 0 END), 0)
+         FROM sent_notifications",
+      )
+      .bind(sqlite_timestamp(since))
+      .fetch_one(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("count sent notifications in sqlite")?;
+
+    Ok(SentStats { total, since })
+  }
+
+  async fn sent_message(&self, thread_id: &str) -> Result<Option<(String, i64)>> {
+    retry_db(self.limits, || {
+      sqlx::query_as::<_, (String, i64)>(
+        "SELECT chat_id, message_id FROM sent_messages WHERE thread_id = ?",
+      )
+      .bind(thread_id)
+      .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("load sent message in sqlite: {thread_id}"))
+  }
+
+  async fn set_sent_message(
+    &self,
+    thread_id: &str,
+    chat_id: &str,
+    message_id: i64,
+  ) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO sent_messages (thread_id, chat_id, message_id)
+         VALUES (?, ?, ?)
+         ON CONFLICT (thread_id) DO UPDATE
+           SET chat_id = excluded.chat_id, message_id = excluded.message_id",
+      )
+      .bind(thread_id)
+      .bind(chat_id)
+      .bind(message_id)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("store sent message in sqlite: {thread_id}"))?;
+
+    Ok(())
+  }
+}
+
+// Inserts or moves the row to a newer updated_at, touching nothing otherwise,
+// so one affected row means the caller holds this version.
+const SQLITE_MARK_SENT: &str =
+  "INSERT INTO sent_notifications (id, chat_id, updated_at)
+  VALUES (?, ?, ?)
+  ON CONFLICT (id, chat_id) DO UPDATE
+    SET updated_at = excluded.updated_at, sent_at = CURRENT_TIMESTAMP
+    WHERE excluded.updated_at > sent_notifications.updated_at";
+
+// Same order and versions as SQLITE_MIGRATIONS.
+const POSTGRES_MIGRATIONS: &[&str] = &[
+  "CREATE TABLE IF NOT EXISTS sent_notifications (
+    id TEXT NOT NULL,
+    chat_id TEXT NOT NULL DEFAULT '',
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id, chat_id)
+  )",
+  "CREATE TABLE IF NOT EXISTS poll_stats (
+    id BIGSERIAL PRIMARY KEY,
+    polled_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    fetched INTEGER NOT NULL,
+    forwarded INTEGER NOT NULL,
+    filtered INTEGER NOT NULL,
+    failed INTEGER NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS pending_group (
+    id TEXT PRIMARY KEY,
+    payload TEXT NOT NULL,
+    html_url TEXT,
+    held_at BIGINT NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS escalations (
+    account TEXT NOT NULL DEFAULT '',
+    thread_id TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    html_url TEXT,
+    first_sent_at BIGINT NOT NULL,
+    level BIGINT NOT NULL,
+    next_at BIGINT NOT NULL,
+    PRIMARY KEY (account, thread_id)
+  )",
+  "CREATE TABLE IF NOT EXISTS feed_entries (
+    seq BIGSERIAL PRIMARY KEY,
+    id TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    title TEXT NOT NULL,
+    html_url TEXT,
+    forwarded_at BIGINT NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS muted_repos (
+    repo TEXT PRIMARY KEY,
+    until BIGINT NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS thread_read_state (
+    thread_id TEXT PRIMARY KEY,
+    unread BOOLEAN NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS audit_log (
+    seq BIGSERIAL PRIMARY KEY,
+    id TEXT NOT NULL,
+    decision TEXT NOT NULL,
+    detail TEXT NOT NULL,
+    recorded_at BIGINT NOT NULL
+  )",
+  // One row per account label; a single account uses the empty label.
+  "CREATE TABLE IF NOT EXISTS poll_state (
+    account TEXT PRIMARY KEY,
+    since_cursor BIGINT NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS repo_topics (
+    chat_id TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    topic_id BIGINT NOT NULL,
+    PRIMARY KEY (chat_id, repo)
+  )",
+  "CREATE TABLE IF NOT EXISTS retry_queue (
+    id TEXT PRIMARY KEY,
+    payload TEXT NOT NULL,
+    html_url TEXT,
+    chat_id TEXT,
+    topic_id BIGINT,
+    attempts BIGINT NOT NULL,
+    next_at BIGINT NOT NULL,
+    dead BOOLEAN NOT NULL
+  )",
+  // The Telegram message last sent for a thread, edited by EDIT_ON_UPDATE.
+  "CREATE TABLE IF NOT EXISTS sent_messages (
+    thread_id TEXT PRIMARY KEY,
+    chat_id TEXT NOT NULL,
+    message_id BIGINT NOT NULL
+  )",
+  "ALTER TABLE pending_group ADD COLUMN IF NOT EXISTS chat_id TEXT",
+  "ALTER TABLE sent_notifications ADD COLUMN IF NOT EXISTS updated_at BIGINT",
+  "CREATE TABLE IF NOT EXISTS poll_source_stats (
+    id BIGSERIAL PRIMARY KEY,
+    polled_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    repo TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    fetched BIGINT NOT NULL,
+    forwarded BIGINT NOT NULL,
+    filtered BIGINT NOT NULL,
+    failed BIGINT NOT NULL
+  )",
+];
+
+// See SQLITE_MARK_SENT.
+const POSTGRES_MARK_SENT: &str =
+  "INSERT INTO sent_notifications (id, chat_id, updated_at)
+  VALUES ($1, $2, $3)
+  ON CONFLICT (id, chat_id) DO UPDATE
+    SET updated_at = excluded.updated_at, sent_at = CURRENT_TIMESTAMP
+    WHERE excluded.updated_at > sent_notifications.updated_at";
+
+impl PostgresStore {
+  // Runs the migrations past the recorded schema_version, each in a
+  // transaction with its version row. The statements are idempotent, so
+  // databases created before schema_version existed start again from 1.
+  async fn migrate(&self) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+                version BIGINT PRIMARY KEY,
+                applied_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+      )
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("create schema_version table in postgres")?;
+
+    let current = retry_db(self.limits, || {
+      sqlx::query_scalar::<_, i64>(
+        "SELECT CAST(COALESCE(MAX(version), 0) AS BIGINT) FROM schema_version",
+      )
+      .fetch_one(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("read schema version in postgres")?;
+    let latest = POSTGRES_MIGRATIONS.len() as i64;
+    if current > latest {
+      bail!(
+        "postgres schema version {current} is newer than this build supports ({latest}), \
+         refusing to start"
+      );
+    }
+
+    for (version, statement) in (1..).zip(POSTGRES_MIGRATIONS).skip(current as usize) {
+      let mut tx = self
+        .pool
+        .begin()
+        .await
+        .context("begin postgres migration")?;
+      sqlx::query(statement)
+        .execute(&mut *tx)
+        .await
+        .map_err(describe_timeout)
+        .with_context(|| format!("apply postgres migration {version}"))?;
+      sqlx::query("INSERT INTO schema_version (version) VALUES ($1)")
+        .bind(version)
+        .execute(&mut *tx)
+        .await
+        .map_err(describe_timeout)
+        .with_context(|| format!("record postgres schema version {version}"))?;
+      tx.commit().await.context("commit postgres migration")?;
+      info!(version, "applied postgres migration");
+    }
+
+    Ok(())
+  }
+
+  // Tables created before per-chat dedupe are keyed by id alone.
+  async fn upgrade_sent_notifications(&self) -> Result<()> {
+    let has_chat_id = sqlx::query_scalar::<_, i64>(
+      "SELECT COUNT(*) FROM information_schema.columns
+         WHERE table_name = 'sent_notifications' AND column_name = 'chat_id'",
+    )
+    .fetch_one(&self.pool)
+    .await
+    .map_err(describe_timeout)
+    .context("inspect sent_notifications table in postgres")?;
+    if has_chat_id == 0 {
+      let mut tx = self.pool.begin().await.context("begin postgres upgrade")?;
+      for statement in [
+        "ALTER TABLE sent_notifications ADD COLUMN chat_id TEXT NOT NULL DEFAULT ''",
+        "ALTER TABLE sent_notifications DROP CONSTRAINT sent_notifications_pkey",
+        "ALTER TABLE sent_notifications ADD PRIMARY KEY (id, chat_id)",
+      ] {
+        sqlx::query(statement)
+          .execute(&mut *tx)
+          .await
+          .map_err(describe_timeout)
+          .context("add chat_id to sent_notifications in postgres")?;
+      }
+      tx.commit().await.context("commit postgres upgrade")?;
+    }
+
+    self.backfill_updated_at().await
+  }
+
+  // Escalations were keyed by thread id alone before multi-account polling;
+  // rows from then belong to the single account, whose label is empty.
+  async fn upgrade_escalations(&self) -> Result<()> {
+    let has_account = sqlx::query_scalar::<_, i64>(
+      "SELECT COUNT(*) FROM information_schema.columns
+         WHERE table_name = 'escalations' AND column_name = 'account'",
+    )
+    .fetch_one(&self.pool)
+    .await
+    .map_err(describe_timeout)
+    .context("inspect escalations table in postgres")?;
+    if has_account > 0 {
+      return Ok(());
+    }
+
+    let mut tx = self.pool.begin().await.context("begin postgres upgrade")?;
+    for statement in [
+      "ALTER TABLE escalations ADD COLUMN account TEXT NOT NULL DEFAULT ''",
+      "ALTER TABLE escalations DROP CONSTRAINT escalations_pkey",
+      "ALTER TABLE escalations ADD PRIMARY KEY (account, thread_id)",
+    ] {
+      sqlx::query(statement)
+        .execute(&mut *tx)
+        .await
+        .map_err(describe_timeout)
+        .context("add account to escalations in postgres")?;
+    }
+    tx.commit().await.context("commit postgres upgrade")?;
+
+    Ok(())
+  }
+
+  async fn backfill_updated_at(&self) -> Result<()> {
+    let mut tx = self.pool.begin().await.context("begin postgres upgrade")?;
+    let rows = sqlx::query_as::<_, (String, String)>(
+      "SELECT id, chat_id FROM sent_notifications WHERE updated_at IS NULL",
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(describe_timeout)
+    .context("load sent notifications without updated_at in postgres")?;
+    if rows.is_empty() {
+      return Ok(());
+    }
+
+    let upgraded = legacy_sent_rows(rows, Utc::now().timestamp());
+    sqlx::query("DELETE FROM sent_notifications WHERE updated_at IS NULL")
+      .execute(&mut *tx)
+      .await
+      .map_err(describe_timeout)
+      .context("drop legacy sent notifications in postgres")?;
+    for ((id, chat_id), updated_at) in &upgraded {
+      sqlx::query(
+        "INSERT INTO sent_notifications (id, chat_id, updated_at) VALUES ($1, $2, $3)
+         ON CONFLICT (id, chat_id) DO UPDATE
+           SET updated_at = GREATEST(sent_notifications.updated_at, excluded.updated_at)",
+      )
+      .bind(id)
+      .bind(chat_id)
+      .bind(updated_at)
+      .execute(&mut *tx)
+      .await
+      .map_err(describe_timeout)
+      .context("add updated_at to sent_notifications in postgres")?;
+    }
+    tx.commit().await.context("commit postgres upgrade")?;
+    info!(
+      rows = upgraded.len(),
+      "added updated_at to sent notifications"
+    );
+
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl NotificationStore for PostgresStore {
+  async fn init(&self) -> Result<()> {
+    self.migrate().await?;
+    self.upgrade_sent_notifications().await?;
+    self.upgrade_escalations().await?;
+
+    // A read-only database would fail every mark_sent after a successful send
+    // and re-send the same notifications forever, so refuse to start instead.
+    let now = Utc::now().timestamp().to_string();
+    self.set_meta(META_WRITE_PROBE, &now).await.context(
+      "database is not writable (read-only file or mount?), refusing to start",
+    )?;
+
+    Ok(())
+  }
+
+  async fn is_sent(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<bool> {
+    let exists = retry_db(self.limits, || {
+      sqlx::query_scalar::<_, i32>(
+        "SELECT 1 FROM sent_notifications
+         WHERE id = $1 AND chat_id = $2 AND updated_at >= $3 LIMIT 1",
+      )
+      .bind(id)
+      .bind(chat_id)
+      .bind(updated_at.timestamp())
+      .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("run postgres dedupe query")?
+    .is_some();
+
+    Ok(exists)
+  }
+
+  async fn mark_sent(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(POSTGRES_MARK_SENT)
+        .bind(id)
+        .bind(chat_id)
+        .bind(updated_at.timestamp())
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("mark notification as sent in postgres: {id}"))?;
+
+    Ok(())
+  }
+
+  async fn try_claim(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<bool> {
+    let result = retry_db(self.limits, || {
+      sqlx::query(POSTGRES_MARK_SENT)
+        .bind(id)
+        .bind(chat_id)
+        .bind(updated_at.timestamp())
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("claim notification in postgres: {id}"))?;
+
+    Ok(result.rows_affected() == 1)
+  }
+
+  // Only undoes this claim; a newer one from another instance stays.
+  async fn release_claim(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "DELETE FROM sent_notifications
+         WHERE id = $1 AND chat_id = $2 AND updated_at = $3",
+      )
+      .bind(id)
+      .bind(chat_id)
+      .bind(updated_at.timestamp())
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("release notification claim in postgres: {id}"))?;
+
+    Ok(())
+  }
+
+  async fn record_poll_stats(&self, stats: &PollStats) -> Result<()> {
+    retry_db(self.limits, || async {
+      let mut tx = self.pool.begin().await?;
+      sqlx::query(
+        "INSERT INTO poll_stats (fetched, forwarded, filtered, failed) VALUES ($1, $2, $3, $4)",
+      )
+      .bind(stats.fetched as i32)
+      .bind(stats.forwarded as i32)
+      .bind(stats.filtered as i32)
+      .bind(stats.failed as i32)
+      .execute(&mut *tx)
+      .await?;
+      for ((repo, reason), counts) in &stats.sources {
+        sqlx::query(
+          "INSERT INTO poll_source_stats
+             (repo, reason, fetched, forwarded, filtered, failed)
+           VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(repo)
+        .bind(reason)
+        .bind(counts.fetched)
+        .bind(counts.forwarded)
+        .bind(counts.filtered)
+        .bind(counts.failed)
+        .execute(&mut *tx)
+        .await?;
+      }
+      tx.commit().await
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("record poll stats in postgres")?;
+
+    Ok(())
+  }
+
+  async fn poll_stats_since(&self, since: DateTime<Utc>) -> Result<PollStatsSummary> {
+    let (polls, fetched, forwarded, filtered, failed) = retry_db(self.limits, || {
+      sqlx::query_as::<_, (i64, i64, i64, i64, i64)>(
+        "SELECT COUNT(*), COALESCE(SUM(fetched), 0)::BIGINT,
+                COALESCE(SUM(forwarded), 0)::BIGINT, COALESCE(SUM(filtered), 0)::BIGINT,
+                COALESCE(SUM(failed), 0)::BIGINT
+           FROM poll_stats WHERE polled_at >= $1",
+      )
+      .bind(since)
+      .fetch_one(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("aggregate poll stats in postgres")?;
+    let sources = retry_db(self.limits, || {
+      sqlx::query_as::<_, SourceRow>(
+        "SELECT repo, reason, SUM(fetched)::BIGINT, SUM(forwarded)::BIGINT,
+                SUM(filtered)::BIGINT, SUM(failed)::BIGINT
+           FROM poll_source_stats WHERE polled_at >= $1 GROUP BY repo, reason",
+      )
+      .bind(since)
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("aggregate poll stats per repo and reason in postgres")?;
+
+    Ok(PollStatsSummary {
+      polls,
+      totals: PollStats {
+        fetched,
+        forwarded,
+        filtered,
+        failed,
+        sources: sources_from_rows(sources),
+      },
+    })
+  }
+
+  async fn hold_pending(
+    &self,
+    id: &str,
+    payload: &str,
+    html_url: Option<&str>,
+    chat_id: Option<&str>,
+  ) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO pending_group (id, payload, html_url, chat_id, held_at)
+       VALUES ($1, $2, $3, $4, $5)
+       ON CONFLICT (id) DO UPDATE SET payload = excluded.payload,
+         html_url = excluded.html_url, chat_id = excluded.chat_id",
+      )
+      .bind(id)
+      .bind(payload)
+      .bind(html_url)
+      .bind(chat_id)
+      .bind(Utc::now().timestamp())
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("hold pending notification in postgres: {id}"))?;
+
+    Ok(())
+  }
+
+  async fn list_pending(&self) -> Result<Vec<PendingNotification>> {
+    let rows = retry_db(self.limits, || {
+      sqlx::query_as::<_, (String, String, Option<String>, Option<String>, i64)>(
+        "SELECT id, payload, html_url, chat_id, held_at
+         FROM pending_group ORDER BY held_at, id",
+      )
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list pending notifications in postgres")?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(
+          |(id, payload, html_url, chat_id, held_at)| PendingNotification {
+            id,
+            payload,
+            html_url,
+            chat_id,
+            held_at,
+          },
+        )
+        .collect(),
+    )
+  }
+
+  async fn remove_pending(&self, id: &str) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM pending_group WHERE id = $1")
+        .bind(id)
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("remove pending notification in postgres: {id}"))?;
+
+    Ok(())
+  }
+
+  async fn get_meta(&self, key: &str) -> Result<Option<String>> {
+    retry_db(self.limits, || {
+      sqlx::query_scalar::<_, String>("SELECT value FROM meta WHERE key = $1")
+        .bind(key)
+        .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("read meta key in postgres: {key}"))
+  }
+
+  async fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO meta (key, value) VALUES ($1, $2)
+         ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+      )
+      .bind(key)
+      .bind(value)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("write meta key in postgres: {key}"))?;
+
+    Ok(())
+  }
+
+  async fn upsert_escalation(&self, escalation: &Escalation) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO escalations
+           (account, thread_id, payload, html_url, first_sent_at, level, next_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         ON CONFLICT (account, thread_id) DO UPDATE SET
+           payload = excluded.payload,
+           html_url = excluded.html_url,
+           first_sent_at = excluded.first_sent_at,
+           level = excluded.level,
+           next_at = excluded.next_at",
+      )
+      .bind(&escalation.account)
+      .bind(&escalation.thread_id)
+      .bind(&escalation.payload)
+      .bind(&escalation.html_url)
+      .bind(escalation.first_sent_at)
+      .bind(escalation.level)
+      .bind(escalation.next_at)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| {
+      format!("upsert escalation in postgres: {}", escalation.thread_id)
+    })?;
+
+    Ok(())
+  }
+
+  async fn due_escalations(&self, now: i64) -> Result<Vec<Escalation>> {
+    let rows = retry_db(self.limits, || {
+      sqlx::query_as::<_, EscalationRow>(
+        "SELECT account, thread_id, payload, html_url, first_sent_at, level, next_at
+         FROM escalations WHERE next_at <= $1 ORDER BY next_at",
+      )
+      .bind(now)
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list due escalations in postgres")?;
+
+    Ok(rows.into_iter().map(escalation_from_row).collect())
+  }
+
+  async fn remove_escalation(&self, account: &str, thread_id: &str) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM escalations WHERE account = $1 AND thread_id = $2")
+        .bind(account)
+        .bind(thread_id)
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("remove escalation in postgres: {thread_id}"))?;
+
+    Ok(())
+  }
+
+  async fn record_feed_entry(&self, entry: &FeedEntry, keep: i64) -> Result<()> {
+    retry_db(self.limits, || sqlx::query(
+      "INSERT INTO feed_entries (id, repo, title, html_url, forwarded_at) VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(&entry.id)
+    .bind(&entry.repo)
+    .bind(&entry.title)
+    .bind(&entry.html_url)
+    .bind(entry.forwarded_at)
+    .execute(&self.pool))
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("record feed entry in postgres: {}", entry.id))?;
+
+    retry_db(self.limits, || {
+      sqlx::query(
+        "DELETE FROM feed_entries
+         WHERE seq NOT IN (SELECT seq FROM feed_entries ORDER BY seq DESC LIMIT $1)",
+      )
+      .bind(keep)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("prune feed entries in postgres")?;
+
+    Ok(())
+  }
+
+  async fn recent_feed_entries(&self, limit: i64) -> Result<Vec<FeedEntry>> {
+    let rows = retry_db(self.limits, || {
+      sqlx::query_as::<_, FeedEntryRow>(
+        "SELECT id, repo, title, html_url, forwarded_at
+         FROM feed_entries ORDER BY seq DESC LIMIT $1",
+      )
+      .bind(limit)
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list feed entries in postgres")?;
+
+    Ok(rows.into_iter().map(feed_entry_from_row).collect())
+  }
+
+  // Clears dedupe state plus the etag, cursors and cooldowns tied to it, so
+  // the next poll forwards everything again. Returns the number of sent rows
+  // removed.
+  async fn reset(&self) -> Result<u64> {
+    let removed = retry_db(self.limits, || {
+      sqlx::query("DELETE FROM sent_notifications").execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("clear sent notifications in postgres")?
+    .rows_affected();
+
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM meta WHERE key = $1 OR key LIKE 'cooldown:%'")
+        .bind(META_NOTIFICATIONS_ETAG)
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("clear dedupe meta in postgres")?;
+
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM poll_state").execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("clear poll cursors in postgres")?;
+
+    Ok(removed)
+  }
+
+  async fn mute_repo(&self, repo: &str, until: i64) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO muted_repos (repo, until) VALUES ($1, $2)
+         ON CONFLICT (repo) DO UPDATE SET until = excluded.until",
+      )
+      .bind(repo)
+      .bind(until)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("mute repo in postgres: {repo}"))?;
+
+    Ok(())
+  }
+
+  // Expired mutes are dropped on read.
+  async fn active_mutes(&self, now: i64) -> Result<Vec<(String, i64)>> {
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM muted_repos WHERE until <= $1")
+        .bind(now)
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("clear expired mutes in postgres")?;
+
+    retry_db(self.limits, || {
+      sqlx::query_as::<_, (String, i64)>("SELECT repo, until FROM muted_repos")
+        .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list muted repos in postgres")
+  }
+
+  async fn read_state(&self, thread_id: &str) -> Result<Option<bool>> {
+    retry_db(self.limits, || {
+      sqlx::query_scalar::<_, bool>(
+        "SELECT unread FROM thread_read_state WHERE thread_id = $1",
+      )
+      .bind(thread_id)
+      .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("load read state in postgres: {thread_id}"))
+  }
+
+  async fn set_read_state(&self, thread_id: &str, unread: bool) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO thread_read_state (thread_id, unread) VALUES ($1, $2)
+         ON CONFLICT (thread_id) DO UPDATE SET unread = excluded.unread",
+      )
+      .bind(thread_id)
+      .bind(unread)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("store read state in postgres: {thread_id}"))?;
+
+    Ok(())
+  }
+
+  async fn audit(&self, entry: &AuditEntry) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO audit_log (id, decision, detail, recorded_at)
+         VALUES ($1, $2, $3, $4)",
+      )
+      .bind(&entry.id)
+      .bind(&entry.decision)
+      .bind(&entry.detail)
+      .bind(entry.recorded_at)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("record audit entry in postgres: {}", entry.id))?;
+
+    Ok(())
+  }
+
+  async fn audit_since(&self, since: i64) -> Result<Vec<AuditEntry>> {
+    let rows = retry_db(self.limits, || {
+      sqlx::query_as::<_, AuditRow>(
+        "SELECT id, decision, detail, recorded_at
+         FROM audit_log WHERE recorded_at >= $1 ORDER BY seq",
+      )
+      .bind(since)
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list audit entries in postgres")?;
+
+    Ok(rows.into_iter().map(audit_entry_from_row).collect())
+  }
+
+  async fn load_cursor(&self, account: &str) -> Result<Option<DateTime<Utc>>> {
+    let cursor = retry_db(self.limits, || {
+      sqlx::query_scalar::<_, i64>(
+        "SELECT since_cursor FROM poll_state WHERE account = $1",
+      )
+      .bind(account)
+      .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("load poll cursor in postgres")?;
+
+    Ok(cursor.and_then(|ts| DateTime::from_timestamp(ts, 0)))
+  }
+
+  async fn save_cursor(&self, account: &str, ts: DateTime<Utc>) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO poll_state (account, since_cursor) VALUES ($1, $2)
+         ON CONFLICT (account) DO UPDATE SET since_cursor = excluded.since_cursor",
+      )
+      .bind(account)
+      .bind(ts.timestamp())
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("save poll cursor in postgres")?;
+
+    Ok(())
+  }
+
+  async fn repo_topic(&self, chat_id: &str, repo: &str) -> Result<Option<i64>> {
+    retry_db(self.limits, || {
+      sqlx::query_scalar::<_, i64>(
+        "SELECT topic_id FROM repo_topics WHERE chat_id = $1 AND repo = $2",
+      )
+      .bind(chat_id)
+      .bind(repo)
+      .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("load repo topic in postgres: {repo}"))
+  }
+
+  async fn set_repo_topic(
+    &self,
+    chat_id: &str,
+    repo: &str,
+    topic_id: i64,
+  ) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO repo_topics (chat_id, repo, topic_id) VALUES ($1, $2, $3)
+         ON CONFLICT (chat_id, repo) DO UPDATE SET topic_id = excluded.topic_id",
+      )
+      .bind(chat_id)
+      .bind(repo)
+      .bind(topic_id)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("store repo topic in postgres: {repo}"))?;
+
+    Ok(())
+  }
+
+  async fn upsert_retry(&self, item: &RetryItem) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO retry_queue
+           (id, payload, html_url, chat_id, topic_id, attempts, next_at, dead)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         ON CONFLICT (id) DO UPDATE SET
+           payload = excluded.payload,
+           html_url = excluded.html_url,
+           chat_id = excluded.chat_id,
+           topic_id = excluded.topic_id,
+           attempts = excluded.attempts,
+           next_at = excluded.next_at,
+           dead = excluded.dead",
+      )
+      .bind(&item.id)
+      .bind(&item.payload)
+      .bind(&item.html_url)
+      .bind(&item.chat_id)
+      .bind(item.topic_id)
+      .bind(item.attempts)
+      .bind(item.next_at)
+      .bind(item.dead)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("upsert retry item in postgres: {}", item.id))?;
+
+    Ok(())
+  }
+
+  async fn due_retries(&self, now: i64) -> Result<Vec<RetryItem>> {
+    let rows = retry_db(self.limits, || {
+      sqlx::query_as::<_, RetryRow>(
+        "SELECT id, payload, html_url, chat_id, topic_id, attempts, next_at, dead
+         FROM retry_queue WHERE NOT dead AND next_at <= $1 ORDER BY next_at",
+      )
+      .bind(now)
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list due retries in postgres")?;
+
+    Ok(rows.into_iter().map(retry_item_from_row).collect())
+  }
+
+  async fn remove_retry(&self, id: &str) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM retry_queue WHERE id = $1")
+        .bind(id)
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("remove retry item in postgres: {id}"))?;
+
+    Ok(())
+  }
+
+  async fn prune(&self, older_than: DateTime<Utc>) -> Result<u64> {
+    let result = retry_db(self.limits, || {
+      sqlx::query("DELETE FROM sent_notifications WHERE sent_at < $1")
+        .bind(older_than)
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("prune sent notifications in postgres")?;
+
+    Ok(result.rows_affected())
+  }
+
+  async fn sent_stats(&self, since: DateTime<Utc>) -> Result<SentStats> {
+    let (total, since) = retry_db(self.limits, || {
+      sqlx::query_as::<_, (i64, i64)>(
+        "SELECT COUNT(*), COALESCE(SUM(CASE WHEN sent_at >= $1 THEN 1 ELSE 0 END), 0)
+         FROM sent_notifications",
+      )
+      .bind(since)
+      .fetch_one(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("count sent notifications in postgres")?;
+
+    Ok(SentStats { total, since })
+  }
+
+  async fn sent_message(&self, thread_id: &str) -> Result<Option<(String, i64)>> {
+    retry_db(self.limits, || {
+      sqlx::query_as::<_, (String, i64)>(
+        "SELECT chat_id, message_id FROM sent_messages WHERE thread_id = $1",
+      )
+      .bind(thread_id)
+      .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("load sent message in postgres: {thread_id}"))
+  }
+
+  async fn set_sent_message(
+    &self,
+    thread_id: &str,
+    chat_id: &str,
+    message_id: i64,
+  ) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO sent_messages (thread_id, chat_id, message_id)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (thread_id) DO UPDATE
+           SET chat_id = excluded.chat_id, message_id = excluded.message_id",
+      )
+      .bind(thread_id)
+      .bind(chat_id)
+      .bind(message_id)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("store sent message in postgres: {thread_id}"))?;
+
+    Ok(())
+  }
+}
+
+fn sqlite_timestamp(ts: DateTime<Utc>) -> String {
+  ts.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// Retries transient failures (dropped connections, deadlocks, busy sqlite
+// databases) with jittered exponential backoff. Anything else, such as syntax
+// or constraint errors, is returned immediately.
+async fn retry_db<T, F, Fut>(limits: QueryLimits, mut op: F) -> Result<T, sqlx::Error>
+where
+  F: FnMut() -> Fut,
+  Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+  let mut attempt = 0;
+  loop {
+    let result = match limits.timeout {
+      Some(timeout) => tokio::time::timeout(timeout, op())
+        .await
+        .unwrap_or_else(|_| Err(query_timed_out())),
+      None => op().await,
+    };
+    match result {
+      Err(err) if attempt < limits.max_retries && is_retryable(&err) => {
+        attempt += 1;
+        tokio::time::sleep(retry_delay(attempt)).await;
+      }
+      result => return result,
+    }
+  }
+}
+
+fn query_timed_out() -> sqlx::Error {
+  sqlx::Error::Io(io::Error::new(io::ErrorKind::TimedOut, "query timed out"))
+}
+
+fn is_retryable(err: &sqlx::Error) -> bool {
+  match err {
+    // A query that used up DB_STATEMENT_TIMEOUT_MS isn't retried: the next
+    // attempt would most likely stall the poll just as long.
+    sqlx::Error::Io(io_err) => io_err.kind() != io::ErrorKind::TimedOut,
+    sqlx::Error::PoolTimedOut => true,
+    // 40001/40P01 are postgres serialization failures and deadlocks, class 08
+    // is connection exceptions, 5/6 are SQLITE_BUSY/SQLITE_LOCKED.
+    sqlx::Error::Database(db_err) => match db_err.code().as_deref() {
+      Some("40001" | "40P01" | "5" | "6") => true,
+      Some(code) => code.starts_with("08"),
+      None => false,
+    },
+    _ => false,
+  }
+}
+
+fn retry_delay(attempt: u32) -> Duration {
+  let base_ms = 100u64 << attempt.saturating_sub(1).min(6);
+  let nanos = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|elapsed| elapsed.subsec_nanos() as u64)
+    .unwrap_or(0);
+  Duration::from_millis(base_ms / 2 + nanos % (base_ms / 2 + 1))
+}
+
+fn describe_timeout(err: sqlx::Error) -> anyhow::Error {
+  let timed_out = match &err {
+    sqlx::Error::PoolTimedOut => true,
+    sqlx::Error::Io(io_err) => io_err.kind() == io::ErrorKind::TimedOut,
+    // 57014 is postgres query_canceled (statement_timeout), 5/6 are sqlite
+    // SQLITE_BUSY/SQLITE_LOCKED once the busy timeout has elapsed.
+    sqlx::Error::Database(db_err) => {
+      matches!(db_err.code().as_deref(), Some("57014" | "5" | "6"))
+    }
+    _ => false,
+  };
+
+  if timed_out {
+    anyhow::Error::new(err).context("database statement timed out")
+  } else {
+    anyhow::Error::new(err)
+  }
+}
+
+fn ensure_sqlite_parent_dir(database_url: &str) -> Result<()> {
+  if database_url == "sqlite::memory:" {
+    return Ok(());
+  }
+
+  let raw_path = database_url.trim_start_matches("sqlite://");
+  if raw_path.is_empty() {
+    return Ok(());
+  }
+
+  let path = Path::new(raw_path);
+  if let Some(parent) = path.parent() {
+    if !parent.as_os_str().is_empty() {
+      std::fs::create_dir_all(parent)
+        .with_context(|| format!("create sqlite dir {}", parent.display()))?;
+    }
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  async fn sqlite_store(limits: QueryLimits) -> SqliteStore {
+    let pool = SqlitePoolOptions::new()
+      .max_connections(1)
+      .connect("sqlite::memory:")
+      .await
+      .unwrap();
+    let store = SqliteStore { pool, limits };
+    store.init().await.unwrap();
+    store
+  }
+
+  fn limits(timeout: Option<Duration>) -> QueryLimits {
+    QueryLimits {
+      max_retries: 3,
+      timeout,
+    }
+  }
+
+  #[tokio::test]
+  async fn slow_query_times_out_once() {
+    let mut attempts = 0;
+    let result: Result<(), _> =
+      retry_db(limits(Some(Duration::from_millis(20))), || {
+        attempts += 1;
+        async {
+          tokio::time::sleep(Duration::from_secs(5)).await;
+          Ok(())
+        }
+      })
+      .await;
+
+    let err = result.map_err(describe_timeout).unwrap_err();
+    assert!(format!("{err:#}").contains("database statement timed out"));
+    assert_eq!(attempts, 1);
+  }
+
+  #[tokio::test]
+  async fn slow_sqlite_query_times_out() {
+    let store = sqlite_store(limits(Some(Duration::from_millis(50)))).await;
+    let result = retry_db(store.limits, || {
+      sqlx::query_scalar::<_, i64>(
+        "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n WHERE x < 1000000000)
+         SELECT MAX(x) FROM n",
+      )
+      .fetch_one(&store.pool)
+    })
+    .await;
+
+    let err = result.map_err(describe_timeout).unwrap_err();
+    assert!(format!("{err:#}").contains("database statement timed out"));
+  }
+
+  #[tokio::test]
+  async fn poll_meta_survives_a_restart() {
+    let store = sqlite_store(limits(None)).await;
+    assert_eq!(PollMeta::load(&store).await.unwrap(), PollMeta::default());
+
+    let mut meta = PollMeta {
+      github_poll_interval: Some(60),
+      ..PollMeta::default()
+    };
+    meta.notifications.insert(
+      "work".to_string(),
+      PageValidators {
+        etag: Some("W/\"abc\"".to_string()),
+        last_modified: Some("Mon, 01 Jan 2024 00:00:00 GMT".to_string()),
+      },
+    );
+    meta.save(&store).await.unwrap();
+    assert_eq!(PollMeta::load(&store).await.unwrap(), meta);
+  }
+
+  #[tokio::test]
+  async fn poll_meta_drops_a_bare_etag() {
+    let store = sqlite_store(limits(None)).await;
+    store
+      .set_meta(META_NOTIFICATIONS_ETAG, "W/\"abc\"")
+      .await
+      .unwrap();
+    assert!(PollMeta::load(&store)
+      .await
+      .unwrap()
+      .notifications
+      .is_empty());
+  }
+
+  #[tokio::test]
+  async fn fast_query_finishes_within_timeout() {
+    let store = sqlite_store(limits(Some(Duration::from_secs(5)))).await;
+    store.mark_sent("1", UNROUTED_CHAT, at(10)).await.unwrap();
+    assert!(store.is_sent("1", UNROUTED_CHAT, at(10)).await.unwrap());
+  }
+
+  fn at(secs: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(secs, 0).unwrap()
+  }
+
+  // The same thread id forwards again once updated_at moves past what was
+  // sent, and stays suppressed for an equal or older one.
+  async fn check_updated_at_dedupe(store: &dyn NotificationStore, id: &str) {
+    assert!(!store.is_sent(id, UNROUTED_CHAT, at(100)).await.unwrap());
+    store.mark_sent(id, UNROUTED_CHAT, at(100)).await.unwrap();
+    assert!(store.is_sent(id, UNROUTED_CHAT, at(100)).await.unwrap());
+    assert!(store.is_sent(id, UNROUTED_CHAT, at(50)).await.unwrap());
+    assert!(!store.is_sent(id, UNROUTED_CHAT, at(200)).await.unwrap());
+    assert!(!store.is_sent(id, "200", at(100)).await.unwrap());
+
+    // An older mark doesn't move the row back.
+    store.mark_sent(id, UNROUTED_CHAT, at(50)).await.unwrap();
+    assert!(store.is_sent(id, UNROUTED_CHAT, at(100)).await.unwrap());
+
+    assert!(!store.try_claim(id, UNROUTED_CHAT, at(100)).await.unwrap());
+    assert!(store.try_claim(id, UNROUTED_CHAT, at(200)).await.unwrap());
+    assert!(!store.try_claim(id, UNROUTED_CHAT, at(200)).await.unwrap());
+    assert!(store.is_sent(id, UNROUTED_CHAT, at(200)).await.unwrap());
+
+    store
+      .release_claim(id, UNROUTED_CHAT, at(100))
+      .await
+      .unwrap();
+    assert!(store.is_sent(id, UNROUTED_CHAT, at(200)).await.unwrap());
+    store
+      .release_claim(id, UNROUTED_CHAT, at(200))
+      .await
+      .unwrap();
+    assert!(!store.is_sent(id, UNROUTED_CHAT, at(200)).await.unwrap());
+  }
+
+  #[tokio::test]
+  async fn sqlite_dedupe_follows_updated_at() {
+    let store = sqlite_store(limits(None)).await;
+    check_updated_at_dedupe(&store, "1").await;
+  }
+
+  #[tokio::test]
+  async fn memory_dedupe_follows_updated_at() {
+    check_updated_at_dedupe(&memory::MemoryStore::default(), "1").await;
+  }
+
+  // A fresh schema on TEST_POSTGRES_URL, or None to skip when it isn't set.
+  async fn postgres_store() -> Option<PostgresStore> {
+    let url = std::env::var("TEST_POSTGRES_URL").ok()?;
+    let schema = format!("test_{}", Utc::now().timestamp_nanos_opt().unwrap());
+    let options = PgConnectOptions::from_str(&url).unwrap();
+    let admin = PgPoolOptions::new()
+      .connect_with(options.clone())
+      .await
+      .unwrap();
+    sqlx::query(&format!("CREATE SCHEMA {schema}"))
+      .execute(&admin)
+      .await
+      .unwrap();
+    let pool = PgPoolOptions::new()
+      .connect_with(options.options([("search_path", schema.as_str())]))
+      .await
+      .unwrap();
+    let store = PostgresStore {
+      pool,
+      limits: limits(None),
+    };
+    store.init().await.unwrap();
+    Some(store)
+  }
+
+  #[tokio::test]
+  async fn postgres_dedupe_follows_updated_at() {
+    if let Some(store) = postgres_store().await {
+      check_updated_at_dedupe(&store, "1").await;
+    }
+  }
+
+  #[test]
+  fn legacy_keys_split_off_their_updated_at() {
+    assert_eq!(
+      split_legacy_key("123:1700000000"),
+      Some(("123", 1700000000))
+    );
+    assert_eq!(
+      split_legacy_key("work:123:1700000000"),
+      Some(("work:123", 1700000000))
+    );
+    assert_eq!(split_legacy_key("123"), None);
+    assert_eq!(split_legacy_key("work:123"), None);
+    assert_eq!(split_legacy_key("event:42"), None);
+  }
+
+  #[tokio::test]
+  async fn sqlite_upgrade_keys_escalations_by_account() {
+    let pool = SqlitePoolOptions::new()
+      .max_connections(1)
+      .connect("sqlite::memory:")
+      .await
+      .unwrap();
+    sqlx::query(
+      "CREATE TABLE escalations (
+         thread_id TEXT PRIMARY KEY,
+         payload TEXT NOT NULL,
+         html_url TEXT,
+         first_sent_at INTEGER NOT NULL,
+         level INTEGER NOT NULL,
+         next_at INTEGER NOT NULL
+       )",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO escalations VALUES ('7', '{}', NULL, 1, 0, 5)")
+      .execute(&pool)
+      .await
+      .unwrap();
+    let store = SqliteStore {
+      pool,
+      limits: limits(None),
+    };
+    store.init().await.unwrap();
+
+    let due = store.due_escalations(10).await.unwrap();
+    assert_eq!(due.len(), 1);
+    assert_eq!(
+      (due[0].account.as_str(), due[0].thread_id.as_str()),
+      ("", "7")
+    );
+    store.remove_escalation("", "7").await.unwrap();
+    assert!(store.due_escalations(10).await.unwrap().is_empty());
+  }
+
+  #[tokio::test]
+  async fn sqlite_upgrade_backfills_updated_at() {
+    let pool = SqlitePoolOptions::new()
+      .max_connections(1)
+      .connect("sqlite::memory:")
+      .await
+      .unwrap();
+    sqlx::query(
+      "CREATE TABLE sent_notifications (
+         id TEXT PRIMARY KEY,
+         sent_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+       )",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    for id in ["1:100", "1:300", "1:200", "work:2"] {
+      sqlx::query("INSERT INTO sent_notifications (id) VALUES (?)")
+        .bind(id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    }
+    let store = SqliteStore {
+      pool,
+      limits: limits(None),
+    };
+    store.init().await.unwrap();
+
+    assert!(store.is_sent("1", UNROUTED_CHAT, at(300)).await.unwrap());
+    assert!(!store.is_sent("1", UNROUTED_CHAT, at(301)).await.unwrap());
+    assert!(store
+      .is_sent("work:2", UNROUTED_CHAT, Utc::now())
+      .await
+      .unwrap());
+    assert!(!store.is_sent("1:300", UNROUTED_CHAT, at(0)).await.unwrap());
+  }
+
+  #[tokio::test]
+  async fn sqlite_upgrade_from_the_baseline_schema() {
+    let pool = SqlitePoolOptions::new()
+      .max_connections(1)
+      .connect("sqlite::memory:")
+      .await
+      .unwrap();
+    sqlx::query(
+      "CREATE TABLE sent_notifications (
+         id TEXT PRIMARY KEY,
+         sent_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+       )",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO sent_notifications VALUES ('42', '2020-01-01 00:00:00')")
+      .execute(&pool)
+      .await
+      .unwrap();
+    let store = SqliteStore {
+      pool,
+      limits: limits(None),
+    };
+    store.init().await.unwrap();
+    store.init().await.unwrap();
+
+    // Baseline rows count as sent up to the upgrade; activity after it forwards
+    // again, and marking it moves the stored updated_at forward.
+    let later = Utc::now() + chrono::Duration::hours(1);
+    assert!(store.is_sent("42", UNROUTED_CHAT, at(100)).await.unwrap());
+    assert!(!store.is_sent("42", UNROUTED_CHAT, later).await.unwrap());
+    store.mark_sent("42", UNROUTED_CHAT, later).await.unwrap();
+    assert!(store.is_sent("42", UNROUTED_CHAT, later).await.unwrap());
+    let newer = later + chrono::Duration::seconds(1);
+    assert!(!store.is_sent("42", UNROUTED_CHAT, newer).await.unwrap());
+    let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sent_notifications")
+      .fetch_one(&store.pool)
+      .await
+      .unwrap();
+    assert_eq!(rows, 1);
+  }
+
+  async fn check_poll_stats_breakdown(store: &dyn NotificationStore) {
+    let since = Utc::now() - chrono::Duration::seconds(5);
+    let mut first = PollStats::default();
+    first.count("octo/a", "mention", StatsOutcome::Fetched);
+    first.count("octo/a", "mention", StatsOutcome::Forwarded);
+    first.count("octo/b", "comment", StatsOutcome::Fetched);
+    first.count("octo/b", "comment", StatsOutcome::Filtered);
+    let mut second = PollStats::default();
+    second.count("octo/a", "mention", StatsOutcome::Fetched);
+    second.count("octo/a", "mention", StatsOutcome::Failed);
+    store.record_poll_stats(&first).await.unwrap();
+    store.record_poll_stats(&second).await.unwrap();
+
+    let summary = store.poll_stats_since(since).await.unwrap();
+    assert_eq!(summary.polls, 2);
+    assert_eq!((summary.totals.fetched, summary.totals.forwarded), (3, 1));
+    let source = |repo: &str, reason: &str| {
+      summary.totals.sources[&(repo.to_string(), reason.to_string())]
+    };
+    assert_eq!(
+      source("octo/a", "mention"),
+      PollCounts {
+        fetched: 2,
+        forwarded: 1,
+        filtered: 0,
+        failed: 1,
+      }
+    );
+    assert_eq!(
+      source("octo/b", "comment"),
+      PollCounts {
+        fetched: 1,
+        forwarded: 0,
+        filtered: 1,
+        failed: 0,
+      }
+    );
+  }
+
+  #[tokio::test]
+  async fn sqlite_poll_stats_aggregate_per_source() {
+    check_poll_stats_breakdown(&sqlite_store(limits(None)).await).await;
+  }
+
+  #[tokio::test]
+  async fn postgres_poll_stats_aggregate_per_source() {
+    if let Some(store) = postgres_store().await {
+      check_poll_stats_breakdown(&store).await;
+    }
+  }
+
+  #[tokio::test]
+  async fn memory_poll_stats_aggregate_per_source() {
+    check_poll_stats_breakdown(&memory::MemoryStore::default()).await;
+  }
+
+  fn store_options(database_url: &str) -> StoreOptions {
+    StoreOptions {
+      database_url: database_url.to_string(),
+      statement_timeout: None,
+      require_tls: false,
+      ca_cert: None,
+      max_retries: 3,
+      max_connections: 5,
+      connect_timeout: Duration::from_secs(2),
+    }
+  }
+
+  #[test]
+  fn required_tls_verifies_the_server() {
+    let plain = postgres_connect_options(&store_options("postgres://db/app")).unwrap();
+    assert!(matches!(plain.get_ssl_mode(), PgSslMode::Prefer));
+
+    let options = StoreOptions {
+      require_tls: true,
+      ca_cert: Some(PathBuf::from("/etc/ssl/db-ca.pem")),
+      statement_timeout: Some(Duration::from_secs(5)),
+      ..store_options("postgres://db/app")
+    };
+    let verified = postgres_connect_options(&options).unwrap();
+    assert!(matches!(verified.get_ssl_mode(), PgSslMode::VerifyFull));
+    assert!(format!("{verified:?}").contains("/etc/ssl/db-ca.pem"));
+    assert_eq!(verified.get_options(), Some("-c statement_timeout=5000"));
+  }
+
+  #[tokio::test]
+  async fn required_tls_fails_against_a_plaintext_server() {
+    let Ok(url) = std::env::var("TEST_POSTGRES_URL") else {
+      return;
+    };
+    let options = StoreOptions {
+      require_tls: true,
+      ..store_options(&url)
+    };
+    let err = connect_store(&options).await.err().unwrap();
+    assert!(format!("{err:#}").contains("with verified TLS required"));
+  }
+
+  fn connection_reset() -> sqlx::Error {
+    sqlx::Error::Io(io::Error::new(io::ErrorKind::ConnectionReset, "reset"))
+  }
+
+  #[tokio::test]
+  async fn store_failing_twice_then_succeeding_is_retried() {
+    let mut attempts = 0;
+    let result = retry_db(limits(None), || {
+      attempts += 1;
+      let attempt = attempts;
+      async move {
+        if attempt <= 2 {
+          Err(connection_reset())
+        } else {
+          Ok(attempt)
+        }
+      }
+    })
+    .await;
+    assert_eq!(result.unwrap(), 3);
+  }
+
+  #[tokio::test]
+  async fn retries_stop_at_the_limit_and_skip_permanent_errors() {
+    let mut attempts = 0;
+    let result: Result<(), _> = retry_db(limits(None), || {
+      attempts += 1;
+      async { Err(connection_reset()) }
+    })
+    .await;
+    assert!(result.is_err());
+    assert_eq!(attempts, 4);
+
+    let mut attempts = 0;
+    let result: Result<(), _> = retry_db(limits(None), || {
+      attempts += 1;
+      async { Err(sqlx::Error::RowNotFound) }
+    })
+    .await;
+    assert!(result.is_err());
+    assert_eq!(attempts, 1);
+  }
+
+  #[test]
+  fn retry_delay_grows_with_jitter() {
+    for attempt in 1..=4 {
+      let base = 100u64 << (attempt - 1);
+      let delay = retry_delay(attempt).as_millis() as u64;
+      assert!((base / 2..=base).contains(&delay), "{attempt}: {delay}");
+    }
+  }
+
+  async fn check_reset(store: &dyn NotificationStore) {
+    store.mark_sent("1", UNROUTED_CHAT, at(100)).await.unwrap();
+    store.mark_sent("2", "200", at(100)).await.unwrap();
+    store.save_cursor("octo", at(100)).await.unwrap();
+    store.set_meta(META_NOTIFICATIONS_ETAG, "{}").await.unwrap();
+    store.set_meta("cooldown:octo/repo", "100").await.unwrap();
+    store.set_meta("other", "kept").await.unwrap();
+
+    assert_eq!(store.reset().await.unwrap(), 2);
+    assert!(!store.is_sent("1", UNROUTED_CHAT, at(100)).await.unwrap());
+    assert!(!store.is_sent("2", "200", at(100)).await.unwrap());
+    assert_eq!(store.load_cursor("octo").await.unwrap(), None);
+    assert_eq!(store.get_meta(META_NOTIFICATIONS_ETAG).await.unwrap(), None);
+    assert_eq!(store.get_meta("cooldown:octo/repo").await.unwrap(), None);
+    assert_eq!(
+      store.get_meta("other").await.unwrap().as_deref(),
+      Some("kept")
+    );
+    assert_eq!(store.reset().await.unwrap(), 0);
+  }
+
+  #[tokio::test]
+  async fn sqlite_reset_clears_dedupe_state() {
+    check_reset(&sqlite_store(limits(None)).await).await;
+  }
+
+  #[tokio::test]
+  async fn memory_reset_clears_dedupe_state() {
+    check_reset(&memory::MemoryStore::default()).await;
+  }
+
+  #[tokio::test]
+  async fn postgres_reset_clears_dedupe_state() {
+    if let Some(store) = postgres_store().await {
+      check_reset(&store).await;
+    }
+  }
+
+  async fn check_cursor_round_trip(store: &dyn NotificationStore) {
+    assert_eq!(store.load_cursor("octo").await.unwrap(), None);
+    let ts = DateTime::parse_from_rfc3339("2024-01-01T12:34:56Z")
+      .unwrap()
+      .with_timezone(&Utc);
+    store.save_cursor("octo", ts).await.unwrap();
+    assert_eq!(store.load_cursor("octo").await.unwrap(), Some(ts));
+    assert_eq!(store.load_cursor("other").await.unwrap(), None);
+
+    let later = ts + chrono::Duration::minutes(5);
+    store.save_cursor("octo", later).await.unwrap();
+    assert_eq!(store.load_cursor("octo").await.unwrap(), Some(later));
+  }
+
+  #[tokio::test]
+  async fn sqlite_cursor_round_trips() {
+    check_cursor_round_trip(&sqlite_store(limits(None)).await).await;
+  }
+
+  #[tokio::test]
+  async fn memory_cursor_round_trips() {
+    check_cursor_round_trip(&memory::MemoryStore::default()).await;
+  }
+
+  #[tokio::test]
+  async fn postgres_cursor_round_trips() {
+    if let Some(store) = postgres_store().await {
+      check_cursor_round_trip(&store).await;
+    }
+  }
+
+  #[tokio::test]
+  async fn read_only_sqlite_refuses_to_start() {
+    let path = std::env::temp_dir().join(format!(
+      "github-notify-to-tg-{}-read-only.db",
+      std::process::id()
+    ));
+    let options = SqliteConnectOptions::new().filename(&path);
+    let pool = SqlitePoolOptions::new()
+      .connect_with(options.clone().create_if_missing(true))
+      .await
+      .unwrap();
+    SqliteStore {
+      pool,
+      limits: limits(None),
+    }
+    .init()
+    .await
+    .unwrap();
+
+    let pool = SqlitePoolOptions::new()
+      .connect_with(options.read_only(true))
+      .await
+      .unwrap();
+    let err = SqliteStore {
+      pool,
+      limits: limits(None),
+    }
+    .init()
+    .await
+    .unwrap_err();
+    assert!(
+      err.to_string().contains("database is not writable"),
+      "{err:#}"
+    );
+    let _ = std::fs::remove_file(&path);
+  }
+
+  #[tokio::test]
+  async fn sqlite_prune_deletes_rows_sent_before_the_cutoff() {
+    let store = sqlite_store(limits(None)).await;
+    let now = Utc::now();
+    for (id, days) in [("old", 40), ("recent", 5)] {
+      store.mark_sent(id, UNROUTED_CHAT, at(100)).await.unwrap();
+      sqlx::query("UPDATE sent_notifications SET sent_at = ? WHERE id = ?")
+        .bind(sqlite_timestamp(now - chrono::Duration::days(days)))
+        .bind(id)
+        .execute(&store.pool)
+        .await
+        .unwrap();
+    }
+
+    let removed = store.prune(now - chrono::Duration::days(30)).await.unwrap();
+    assert_eq!(removed, 1);
+    assert!(!store.is_sent("old", UNROUTED_CHAT, at(100)).await.unwrap());
+    assert!(store
+      .is_sent("recent", UNROUTED_CHAT, at(100))
+      .await
+      .unwrap());
+  }
+
+  #[tokio::test]
+  async fn postgres_prune_deletes_rows_sent_before_the_cutoff() {
+    let Some(store) = postgres_store().await else {
+      return;
+    };
+    let now = Utc::now();
+    for (id, days) in [("old", 40), ("recent", 5)] {
+      store.mark_sent(id, UNROUTED_CHAT, at(100)).await.unwrap();
+      sqlx::query("UPDATE sent_notifications SET sent_at = $1 WHERE id = $2")
+        .bind(now - chrono::Duration::days(days))
+        .bind(id)
+        .execute(&store.pool)
+        .await
+        .unwrap();
+    }
+
+    let removed = store.prune(now - chrono::Duration::days(30)).await.unwrap();
+    assert_eq!(removed, 1);
+    assert!(!store.is_sent("old", UNROUTED_CHAT, at(100)).await.unwrap());
+    assert!(store
+      .is_sent("recent", UNROUTED_CHAT, at(100))
+      .await
+      .unwrap());
+  }
+
+  #[tokio::test]
+  async fn memory_prune_keeps_rows_sent_after_the_cutoff() {
+    let store = memory::MemoryStore::default();
+    store.mark_sent("1", UNROUTED_CHAT, at(100)).await.unwrap();
+    let hour = chrono::Duration::hours(1);
+    assert_eq!(store.prune(Utc::now() - hour).await.unwrap(), 0);
+    assert_eq!(store.prune(Utc::now() + hour).await.unwrap(), 1);
+    assert!(!store.is_sent("1", UNROUTED_CHAT, at(100)).await.unwrap());
+  }
+
+  #[tokio::test]
+  async fn memory_stores_do_not_share_state() {
+    let options = store_options("memory://");
+    let first = connect_store(&options).await.unwrap();
+    let second = connect_store(&options).await.unwrap();
+    first.init().await.unwrap();
+    second.init().await.unwrap();
+
+    first.mark_sent("1", UNROUTED_CHAT, at(100)).await.unwrap();
+    assert!(first.is_sent("1", UNROUTED_CHAT, at(100)).await.unwrap());
+    assert!(!second.is_sent("1", UNROUTED_CHAT, at(100)).await.unwrap());
+  }
+
+  #[tokio::test]
+  async fn sent_stats_count_rows_since_the_cutoff() {
+    let store = sqlite_store(limits(None)).await;
+    let now = Utc::now();
+    for (id, hours) in [("old", 48), ("day", 20), ("recent", 1)] {
+      store.mark_sent(id, UNROUTED_CHAT, at(100)).await.unwrap();
+      sqlx::query("UPDATE sent_notifications SET sent_at = ? WHERE id = ?")
+        .bind(sqlite_timestamp(now - chrono::Duration::hours(hours)))
+        .bind(id)
+        .execute(&store.pool)
+        .await
+        .unwrap();
+    }
+
+    let stats = store
+      .sent_stats(now - chrono::Duration::hours(24))
+      .await
+      .unwrap();
+    assert_eq!((stats.total, stats.since), (3, 2));
+    let stats = store
+      .sent_stats(now - chrono::Duration::hours(2))
+      .await
+      .unwrap();
+    assert_eq!((stats.total, stats.since), (3, 1));
+    let stats = memory::MemoryStore::default()
+      .sent_stats(now - chrono::Duration::hours(24))
+      .await
+      .unwrap();
+    assert_eq!((stats.total, stats.since), (0, 0));
+  }
+
+  #[tokio::test]
+  async fn sqlite_init_twice_keeps_the_latest_version() {
+    let store = sqlite_store(limits(None)).await;
+    store.init().await.unwrap();
+    let version: i64 = sqlx::query_scalar("SELECT MAX(version) FROM schema_version")
+      .fetch_one(&store.pool)
+      .await
+      .unwrap();
+    assert_eq!(version, SQLITE_MIGRATIONS.len() as i64);
+  }
+
+  #[tokio::test]
+  async fn postgres_init_twice_keeps_the_latest_version() {
+    let Some(store) = postgres_store().await else {
+      return;
+    };
+    store.init().await.unwrap();
+    let version: i64 =
+      sqlx::query_scalar("SELECT CAST(MAX(version) AS BIGINT) FROM schema_version")
+        .fetch_one(&store.pool)
+        .await
+        .unwrap();
+    assert_eq!(version, POSTGRES_MIGRATIONS.len() as i64);
+  }
+
+  async fn check_sent_message_round_trip(store: &dyn NotificationStore) {
+    assert_eq!(store.sent_message("1").await.unwrap(), None);
+    store.set_sent_message("1", "100", 7).await.unwrap();
+    assert_eq!(
+      store.sent_message("1").await.unwrap(),
+      Some(("100".to_string(), 7))
+    );
+    // A new message for the thread replaces the old one.
+    store.set_sent_message("1", "200", 8).await.unwrap();
+    assert_eq!(
+      store.sent_message("1").await.unwrap(),
+      Some(("200".to_string(), 8))
+    );
+    assert_eq!(store.sent_message("2").await.unwrap(), None);
+  }
+
+  #[tokio::test]
+  async fn sqlite_sent_message_round_trips() {
+    check_sent_message_round_trip(&sqlite_store(limits(None)).await).await;
+  }
+
+  #[tokio::test]
+  async fn memory_sent_message_round_trips() {
+    check_sent_message_round_trip(&memory::MemoryStore::default()).await;
+  }
+
+  #[tokio::test]
+  async fn postgres_sent_message_round_trips() {
+    if let Some(store) = postgres_store().await {
+      check_sent_message_round_trip(&store).await;
+    }
+  }
 }
@@ -0,0 +1,385 @@
+use super::*;
+use std::collections::HashMap;
+use std::sync::{MutexGuard, PoisonError};
+
+// `memory://`: everything lives in the process and is gone on exit, so a
+// restart forwards the current inbox again. Meant for dry runs and throwaway
+// deployments.
+#[derive(Default)]
+pub struct MemoryStore {
+  state: Mutex<MemoryState>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+  // Keyed by (id, chat_id), with the latest updated_at sent and when.
+  sent: HashMap<(String, String), (DateTime<Utc>, DateTime<Utc>)>,
+  poll_stats: Vec<(DateTime<Utc>, PollStats)>,
+  pending: HashMap<String, PendingNotification>,
+  meta: HashMap<String, String>,
+  escalations: HashMap<(String, String), EscalationRow>,
+  feed: Vec<FeedEntryRow>,
+  mutes: HashMap<String, i64>,
+  read_state: HashMap<String, bool>,
+  audit: Vec<AuditRow>,
+  cursors: HashMap<String, DateTime<Utc>>,
+  topics: HashMap<(String, String), i64>,
+  retries: HashMap<String, RetryRow>,
+  sent_messages: HashMap<String, (String, i64)>,
+}
+
+impl MemoryStore {
+  fn state(&self) -> MutexGuard<'_, MemoryState> {
+    self.state.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+}
+
+#[async_trait]
+impl NotificationStore for MemoryStore {
+  async fn init(&self) -> Result<()> {
+    Ok(())
+  }
+
+  async fn is_sent(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<bool> {
+    let key = (id.to_string(), chat_id.to_string());
+    let sent = self.state().sent.get(&key).map(|(latest, _)| *latest);
+    Ok(sent >= Some(updated_at))
+  }
+
+  async fn mark_sent(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<()> {
+    self.try_claim(id, chat_id, updated_at).await?;
+    Ok(())
+  }
+
+  async fn try_claim(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<bool> {
+    let key = (id.to_string(), chat_id.to_string());
+    let mut state = self.state();
+    if state
+      .sent
+      .get(&key)
+      .is_some_and(|(latest, _)| *latest >= updated_at)
+    {
+      return Ok(false);
+    }
+    state.sent.insert(key, (updated_at, Utc::now()));
+    Ok(true)
+  }
+
+  async fn release_claim(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<()> {
+    let key = (id.to_string(), chat_id.to_string());
+    let mut state = self.state();
+    if state
+      .sent
+      .get(&key)
+      .is_some_and(|(latest, _)| *latest == updated_at)
+    {
+      state.sent.remove(&key);
+    }
+    Ok(())
+  }
+
+  async fn record_poll_stats(&self, stats: &PollStats) -> Result<()> {
+    self.state().poll_stats.push((Utc::now(), stats.clone()));
+    Ok(())
+  }
+
+  async fn poll_stats_since(&self, since: DateTime<Utc>) -> Result<PollStatsSummary> {
+    let state = self.state();
+    let mut summary = PollStatsSummary::default();
+    for (_, stats) in state.poll_stats.iter().filter(|(at, _)| *at >= since) {
+      summary.polls += 1;
+      summary.totals.fetched += stats.fetched;
+      summary.totals.forwarded += stats.forwarded;
+      summary.totals.filtered += stats.filtered;
+      summary.totals.failed += stats.failed;
+      for (source, counts) in &stats.sources {
+        summary
+          .totals
+          .sources
+          .entry(source.clone())
+          .or_default()
+          .add(counts);
+      }
+    }
+    Ok(summary)
+  }
+
+  async fn hold_pending(
+    &self,
+    id: &str,
+    payload: &str,
+    html_url: Option<&str>,
+    chat_id: Option<&str>,
+  ) -> Result<()> {
+    let mut state = self.state();
+    let held_at = state
+      .pending
+      .get(id)
+      .map_or_else(|| Utc::now().timestamp(), |held| held.held_at);
+    let held = PendingNotification {
+      id: id.to_string(),
+      payload: payload.to_string(),
+      html_url: html_url.map(str::to_string),
+      chat_id: chat_id.map(str::to_string),
+      held_at,
+    };
+    state.pending.insert(id.to_string(), held);
+    Ok(())
+  }
+
+  async fn list_pending(&self) -> Result<Vec<PendingNotification>> {
+    let mut pending: Vec<_> = self.state().pending.values().cloned().collect();
+    pending.sort_by(|a, b| (a.held_at, &a.id).cmp(&(b.held_at, &b.id)));
+    Ok(pending)
+  }
+
+  async fn remove_pending(&self, id: &str) -> Result<()> {
+    self.state().pending.remove(id);
+    Ok(())
+  }
+
+  async fn get_meta(&self, key: &str) -> Result<Option<String>> {
+    Ok(self.state().meta.get(key).cloned())
+  }
+
+  async fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+    self.state().meta.insert(key.to_string(), value.to_string());
+    Ok(())
+  }
+
+  async fn upsert_escalation(&self, escalation: &Escalation) -> Result<()> {
+    self.state().escalations.insert(
+      (escalation.account.clone(), escalation.thread_id.clone()),
+      (
+        escalation.account.clone(),
+        escalation.thread_id.clone(),
+        escalation.payload.clone(),
+        escalation.html_url.clone(),
+        escalation.first_sent_at,
+        escalation.level,
+        escalation.next_at,
+      ),
+    );
+    Ok(())
+  }
+
+  async fn due_escalations(&self, now: i64) -> Result<Vec<Escalation>> {
+    let mut rows: Vec<_> = self
+      .state()
+      .escalations
+      .values()
+      .filter(|row| row.6 <= now)
+      .cloned()
+      .collect();
+    rows.sort_by_key(|row| row.6);
+    Ok(rows.into_iter().map(escalation_from_row).collect())
+  }
+
+  async fn remove_escalation(&self, account: &str, thread_id: &str) -> Result<()> {
+    let key = (account.to_string(), thread_id.to_string());
+    self.state().escalations.remove(&key);
+    Ok(())
+  }
+
+  async fn record_feed_entry(&self, entry: &FeedEntry, keep: i64) -> Result<()> {
+    let mut state = self.state();
+    state.feed.push((
+      entry.id.clone(),
+      entry.repo.clone(),
+      entry.title.clone(),
+      entry.html_url.clone(),
+      entry.forwarded_at,
+    ));
+    let excess = state.feed.len().saturating_sub(keep.max(0) as usize);
+    state.feed.drain(..excess);
+    Ok(())
+  }
+
+  async fn recent_feed_entries(&self, limit: i64) -> Result<Vec<FeedEntry>> {
+    Ok(
+      self
+        .state()
+        .feed
+        .iter()
+        .rev()
+        .take(limit.max(0) as usize)
+        .cloned()
+        .map(feed_entry_from_row)
+        .collect(),
+    )
+  }
+
+  async fn reset(&self) -> Result<u64> {
+    let mut state = self.state();
+    let removed = state.sent.len() as u64;
+    state.sent.clear();
+    state
+      .meta
+      .retain(|key, _| key != META_NOTIFICATIONS_ETAG && !key.starts_with("cooldown:"));
+    state.cursors.clear();
+    Ok(removed)
+  }
+
+  async fn mute_repo(&self, repo: &str, until: i64) -> Result<()> {
+    self.state().mutes.insert(repo.to_string(), until);
+    Ok(())
+  }
+
+  async fn active_mutes(&self, now: i64) -> Result<Vec<(String, i64)>> {
+    let mut state = self.state();
+    state.mutes.retain(|_, until| *until > now);
+    Ok(
+      state
+        .mutes
+        .iter()
+        .map(|(repo, until)| (repo.clone(), *until))
+        .collect(),
+    )
+  }
+
+  async fn read_state(&self, thread_id: &str) -> Result<Option<bool>> {
+    Ok(self.state().read_state.get(thread_id).copied())
+  }
+
+  async fn set_read_state(&self, thread_id: &str, unread: bool) -> Result<()> {
+    self
+      .state()
+      .read_state
+      .insert(thread_id.to_string(), unread);
+    Ok(())
+  }
+
+  async fn audit(&self, entry: &AuditEntry) -> Result<()> {
+    self.state().audit.push((
+      entry.id.clone(),
+      entry.decision.clone(),
+      entry.detail.clone(),
+      entry.recorded_at,
+    ));
+    Ok(())
+  }
+
+  async fn audit_since(&self, since: i64) -> Result<Vec<AuditEntry>> {
+    Ok(
+      self
+        .state()
+        .audit
+        .iter()
+        .filter(|row| row.3 >= since)
+        .cloned()
+        .map(audit_entry_from_row)
+        .collect(),
+    )
+  }
+
+  async fn load_cursor(&self, account: &str) -> Result<Option<DateTime<Utc>>> {
+    Ok(self.state().cursors.get(account).copied())
+  }
+
+  async fn save_cursor(&self, account: &str, ts: DateTime<Utc>) -> Result<()> {
+    self.state().cursors.insert(account.to_string(), ts);
+    Ok(())
+  }
+
+  async fn repo_topic(&self, chat_id: &str, repo: &str) -> Result<Option<i64>> {
+    let key = (chat_id.to_string(), repo.to_string());
+    Ok(self.state().topics.get(&key).copied())
+  }
+
+  async fn set_repo_topic(
+    &self,
+    chat_id: &str,
+    repo: &str,
+    topic_id: i64,
+  ) -> Result<()> {
+    let key = (chat_id.to_string(), repo.to_string());
+    self.state().topics.insert(key, topic_id);
+    Ok(())
+  }
+
+  async fn upsert_retry(&self, item: &RetryItem) -> Result<()> {
+    self.state().retries.insert(
+      item.id.clone(),
+      (
+        item.id.clone(),
+        item.payload.clone(),
+        item.html_url.clone(),
+        item.chat_id.clone(),
+        item.topic_id,
+        item.attempts,
+        item.next_at,
+        item.dead,
+      ),
+    );
+    Ok(())
+  }
+
+  async fn due_retries(&self, now: i64) -> Result<Vec<RetryItem>> {
+    let mut rows: Vec<_> = self
+      .state()
+      .retries
+      .values()
+      .filter(|row| !row.7 && row.6 <= now)
+      .cloned()
+      .collect();
+    rows.sort_by_key(|row| row.6);
+    Ok(rows.into_iter().map(retry_item_from_row).collect())
+  }
+
+  async fn remove_retry(&self, id: &str) -> Result<()> {
+    self.state().retries.remove(id);
+    Ok(())
+  }
+
+  async fn prune(&self, older_than: DateTime<Utc>) -> Result<u64> {
+    let mut state = self.state();
+    let before = state.sent.len();
+    state.sent.retain(|_, (_, sent_at)| *sent_at >= older_than);
+    Ok((before - state.sent.len()) as u64)
+  }
+
+  async fn sent_stats(&self, since: DateTime<Utc>) -> Result<SentStats> {
+    let state = self.state();
+    Ok(SentStats {
+      total: state.sent.len() as i64,
+      since: state.sent.values().filter(|(_, at)| *at >= since).count() as i64,
+    })
+  }
+
+  async fn sent_message(&self, thread_id: &str) -> Result<Option<(String, i64)>> {
+    Ok(self.state().sent_messages.get(thread_id).cloned())
+  }
+
+  async fn set_sent_message(
+    &self,
+    thread_id: &str,
+    chat_id: &str,
+    message_id: i64,
+  ) -> Result<()> {
+    self
+      .state()
+      .sent_messages
+      .insert(thread_id.to_string(), (chat_id.to_string(), message_id));
+    Ok(())
+  }
+}
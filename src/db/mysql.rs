@@ -0,0 +1,988 @@
+use super::*;
+use sqlx::mysql::{MySqlConnectOptions, MySqlPoolOptions, MySqlSslMode};
+use sqlx::MySqlPool;
+
+pub struct MySqlStore {
+  pool: MySqlPool,
+  limits: QueryLimits,
+}
+
+pub async fn connect(options: &StoreOptions) -> Result<Box<dyn NotificationStore>> {
+  let database_url = options.database_url.as_str();
+  let mut connect_options = MySqlConnectOptions::from_str(database_url)
+    .with_context(|| format!("parse mysql database url: {database_url}"))?;
+  if options.require_tls {
+    connect_options = connect_options.ssl_mode(MySqlSslMode::VerifyIdentity);
+  }
+  if let Some(ca_cert) = &options.ca_cert {
+    connect_options = connect_options.ssl_ca(ca_cert);
+  }
+
+  let mut pool_options = MySqlPoolOptions::new()
+    .max_connections(options.max_connections)
+    .acquire_timeout(options.connect_timeout);
+  if let Some(timeout) = options.statement_timeout {
+    let statement = format!("SET SESSION max_execution_time = {}", timeout.as_millis());
+    pool_options = pool_options.after_connect(move |conn, _| {
+      let statement = statement.clone();
+      Box::pin(async move {
+        sqlx::query(&statement).execute(conn).await?;
+        Ok(())
+      })
+    });
+  }
+
+  let pool = pool_options
+    .connect_with(connect_options)
+    .await
+    .with_context(|| {
+      if options.require_tls {
+        format!("connect mysql database with verified TLS required: {database_url}")
+      } else {
+        format!("connect mysql database: {database_url}")
+      }
+    })?;
+  Ok(Box::new(MySqlStore {
+    pool,
+    limits: QueryLimits::new(options),
+  }))
+}
+
+// Same order and versions as the sqlite and postgres lists.
+const MYSQL_MIGRATIONS: &[&str] = &[
+  "CREATE TABLE IF NOT EXISTS sent_notifications (
+    id VARCHAR(255) NOT NULL,
+    chat_id VARCHAR(255) NOT NULL DEFAULT '',
+    sent_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id, chat_id)
+  )",
+  "CREATE TABLE IF NOT EXISTS poll_stats (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    polled_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    fetched INTEGER NOT NULL,
+    forwarded INTEGER NOT NULL,
+    filtered INTEGER NOT NULL,
+    failed INTEGER NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS pending_group (
+    id VARCHAR(255) PRIMARY KEY,
+    payload TEXT NOT NULL,
+    html_url TEXT,
+    held_at BIGINT NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS meta (
+    `key` VARCHAR(255) PRIMARY KEY,
+    value TEXT NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS escalations (
+    account VARCHAR(255) NOT NULL DEFAULT '',
+    thread_id VARCHAR(255) NOT NULL,
+    payload TEXT NOT NULL,
+    html_url TEXT,
+    first_sent_at BIGINT NOT NULL,
+    level BIGINT NOT NULL,
+    next_at BIGINT NOT NULL,
+    PRIMARY KEY (account, thread_id)
+  )",
+  "CREATE TABLE IF NOT EXISTS feed_entries (
+    seq BIGINT AUTO_INCREMENT PRIMARY KEY,
+    id TEXT NOT NULL,
+    repo TEXT NOT NULL,
+    title TEXT NOT NULL,
+    html_url TEXT,
+    forwarded_at BIGINT NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS muted_repos (
+    repo VARCHAR(255) PRIMARY KEY,
+    until BIGINT NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS thread_read_state (
+    thread_id VARCHAR(255) PRIMARY KEY,
+    unread BOOLEAN NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS audit_log (
+    seq BIGINT AUTO_INCREMENT PRIMARY KEY,
+    id TEXT NOT NULL,
+    decision TEXT NOT NULL,
+    detail TEXT NOT NULL,
+    recorded_at BIGINT NOT NULL
+  )",
+  // One row per account label; a single account uses the empty label.
+  "CREATE TABLE IF NOT EXISTS poll_state (
+    account VARCHAR(255) PRIMARY KEY,
+    since_cursor BIGINT NOT NULL
+  )",
+  "CREATE TABLE IF NOT EXISTS repo_topics (
+    chat_id VARCHAR(255) NOT NULL,
+    repo VARCHAR(255) NOT NULL,
+    topic_id BIGINT NOT NULL,
+    PRIMARY KEY (chat_id, repo)
+  )",
+  "CREATE TABLE IF NOT EXISTS retry_queue (
+    id VARCHAR(255) PRIMARY KEY,
+    payload TEXT NOT NULL,
+    html_url TEXT,
+    chat_id TEXT,
+    topic_id BIGINT,
+    attempts BIGINT NOT NULL,
+    next_at BIGINT NOT NULL,
+    dead BOOLEAN NOT NULL
+  )",
+  // The Telegram message last sent for a thread, edited by EDIT_ON_UPDATE.
+  "CREATE TABLE IF NOT EXISTS sent_messages (
+    thread_id VARCHAR(255) PRIMARY KEY,
+    chat_id TEXT NOT NULL,
+    message_id BIGINT NOT NULL
+  )",
+  "ALTER TABLE pending_group ADD COLUMN chat_id TEXT",
+  "ALTER TABLE sent_notifications ADD COLUMN updated_at BIGINT",
+  "CREATE TABLE IF NOT EXISTS poll_source_stats (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    polled_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    repo VARCHAR(255) NOT NULL,
+    reason VARCHAR(255) NOT NULL,
+    fetched BIGINT NOT NULL,
+    forwarded BIGINT NOT NULL,
+    filtered BIGINT NOT NULL,
+    failed BIGINT NOT NULL
+  )",
+];
+
+impl MySqlStore {
+  // Runs the migrations past the recorded schema_version, each in a
+  // transaction with its version row. The statements are idempotent, so
+  // databases created before schema_version existed start again from 1.
+  async fn migrate(&self) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+                version BIGINT PRIMARY KEY,
+                applied_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+      )
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("create schema_version table in mysql")?;
+
+    let current = retry_db(self.limits, || {
+      sqlx::query_scalar::<_, i64>(
+        "SELECT CAST(COALESCE(MAX(version), 0) AS SIGNED) FROM schema_version",
+      )
+      .fetch_one(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("read schema version in mysql")?;
+    let latest = MYSQL_MIGRATIONS.len() as i64;
+    if current > latest {
+      bail!(
+        "mysql schema version {current} is newer than this build supports ({latest}), \
+         refusing to start"
+      );
+    }
+
+    for (version, statement) in (1..).zip(MYSQL_MIGRATIONS).skip(current as usize) {
+      let mut tx = self.pool.begin().await.context("begin mysql migration")?;
+      sqlx::query(statement)
+        .execute(&mut *tx)
+        .await
+        .map_err(describe_timeout)
+        .with_context(|| format!("apply mysql migration {version}"))?;
+      sqlx::query("INSERT INTO schema_version (version) VALUES (?)")
+        .bind(version)
+        .execute(&mut *tx)
+        .await
+        .map_err(describe_timeout)
+        .with_context(|| format!("record mysql schema version {version}"))?;
+      tx.commit().await.context("commit mysql migration")?;
+      info!(version, "applied mysql migration");
+    }
+
+    Ok(())
+  }
+
+  // MySQL reports matched rather than changed rows for an upsert, so the
+  // insert and the move to a newer updated_at are separate statements; each
+  // affects a row only when this caller holds the version.
+  async fn claim(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<bool> {
+    let inserted = retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT IGNORE INTO sent_notifications (id, chat_id, updated_at) VALUES (?, ?, ?)",
+      )
+      .bind(id)
+      .bind(chat_id)
+      .bind(updated_at.timestamp())
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)?;
+    if inserted.rows_affected() == 1 {
+      return Ok(true);
+    }
+
+    let moved = retry_db(self.limits, || {
+      sqlx::query(
+        "UPDATE sent_notifications SET updated_at = ?, sent_at = CURRENT_TIMESTAMP
+         WHERE id = ? AND chat_id = ? AND updated_at < ?",
+      )
+      .bind(updated_at.timestamp())
+      .bind(id)
+      .bind(chat_id)
+      .bind(updated_at.timestamp())
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)?;
+
+    Ok(moved.rows_affected() == 1)
+  }
+
+  // Rows from before updated_at was tracked; see legacy_sent_rows.
+  async fn backfill_updated_at(&self) -> Result<()> {
+    let mut tx = self.pool.begin().await.context("begin mysql upgrade")?;
+    let rows = sqlx::query_as::<_, (String, String)>(
+      "SELECT id, chat_id FROM sent_notifications WHERE updated_at IS NULL",
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(describe_timeout)
+    .context("load sent notifications without updated_at in mysql")?;
+    if rows.is_empty() {
+      return Ok(());
+    }
+
+    let upgraded = legacy_sent_rows(rows, Utc::now().timestamp());
+    sqlx::query("DELETE FROM sent_notifications WHERE updated_at IS NULL")
+      .execute(&mut *tx)
+      .await
+      .map_err(describe_timeout)
+      .context("drop legacy sent notifications in mysql")?;
+    for ((id, chat_id), updated_at) in &upgraded {
+      sqlx::query(
+        "INSERT INTO sent_notifications (id, chat_id, updated_at) VALUES (?, ?, ?)
+         ON DUPLICATE KEY UPDATE updated_at = GREATEST(updated_at, VALUES(updated_at))",
+      )
+      .bind(id)
+      .bind(chat_id)
+      .bind(updated_at)
+      .execute(&mut *tx)
+      .await
+      .map_err(describe_timeout)
+      .context("add updated_at to sent_notifications in mysql")?;
+    }
+    tx.commit().await.context("commit mysql upgrade")?;
+    info!(
+      rows = upgraded.len(),
+      "added updated_at to sent notifications"
+    );
+
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl NotificationStore for MySqlStore {
+  async fn init(&self) -> Result<()> {
+    self.migrate().await?;
+    self.backfill_updated_at().await?;
+
+    // A read-only database would fail every mark_sent after a successful send
+    // and re-send the same notifications forever, so refuse to start instead.
+    let now = Utc::now().timestamp().to_string();
+    self.set_meta(META_WRITE_PROBE, &now).await.context(
+      "database is not writable (read-only file or mount?), refusing to start",
+    )?;
+
+    Ok(())
+  }
+
+  async fn is_sent(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<bool> {
+    let exists = retry_db(self.limits, || {
+      sqlx::query_scalar::<_, i64>(
+        "SELECT 1 FROM sent_notifications
+         WHERE id = ? AND chat_id = ? AND updated_at >= ? LIMIT 1",
+      )
+      .bind(id)
+      .bind(chat_id)
+      .bind(updated_at.timestamp())
+      .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("run mysql dedupe query")?
+    .is_some();
+
+    Ok(exists)
+  }
+
+  async fn mark_sent(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<()> {
+    self
+      .claim(id, chat_id, updated_at)
+      .await
+      .with_context(|| format!("mark notification as sent in mysql: {id}"))?;
+
+    Ok(())
+  }
+
+  async fn try_claim(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<bool> {
+    self
+      .claim(id, chat_id, updated_at)
+      .await
+      .with_context(|| format!("claim notification in mysql: {id}"))
+  }
+
+  // Only undoes this claim; a newer one from another instance stays.
+  async fn release_claim(
+    &self,
+    id: &str,
+    chat_id: &str,
+    updated_at: DateTime<Utc>,
+  ) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "DELETE FROM sent_notifications WHERE id = ? AND chat_id = ? AND updated_at = ?",
+      )
+      .bind(id)
+      .bind(chat_id)
+      .bind(updated_at.timestamp())
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("release notification claim in mysql: {id}"))?;
+
+    Ok(())
+  }
+
+  async fn record_poll_stats(&self, stats: &PollStats) -> Result<()> {
+    retry_db(self.limits, || async {
+      let mut tx = self.pool.begin().await?;
+      sqlx::query(
+        "INSERT INTO poll_stats (fetched, forwarded, filtered, failed) VALUES (?, ?, ?, ?)",
+      )
+      .bind(stats.fetched as i32)
+      .bind(stats.forwarded as i32)
+      .bind(stats.filtered as i32)
+      .bind(stats.failed as i32)
+      .execute(&mut *tx)
+      .await?;
+      for ((repo, reason), counts) in &stats.sources {
+        sqlx::query(
+          "INSERT INTO poll_source_stats
+             (repo, reason, fetched, forwarded, filtered, failed)
+           VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(repo)
+        .bind(reason)
+        .bind(counts.fetched)
+        .bind(counts.forwarded)
+        .bind(counts.filtered)
+        .bind(counts.failed)
+        .execute(&mut *tx)
+        .await?;
+      }
+      tx.commit().await
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("record poll stats in mysql")?;
+
+    Ok(())
+  }
+
+  async fn poll_stats_since(&self, since: DateTime<Utc>) -> Result<PollStatsSummary> {
+    let (polls, fetched, forwarded, filtered, failed) =
+      retry_db(self.limits, || {
+        sqlx::query_as::<_, (i64, i64, i64, i64, i64)>(
+          "SELECT COUNT(*), CAST(COALESCE(SUM(fetched), 0) AS SIGNED),
+                CAST(COALESCE(SUM(forwarded), 0) AS SIGNED), CAST(COALESCE(SUM(filtered), 0) AS SIGNED),
+                CAST(COALESCE(SUM(failed), 0) AS SIGNED)
+           FROM poll_stats WHERE polled_at >= ?",
+        )
+        .bind(since)
+        .fetch_one(&self.pool)
+      })
+      .await
+      .map_err(describe_timeout)
+      .context("aggregate poll stats in mysql")?;
+    let sources = retry_db(self.limits, || {
+      sqlx::query_as::<_, SourceRow>(
+        "SELECT repo, reason, CAST(SUM(fetched) AS SIGNED), CAST(SUM(forwarded) AS SIGNED),
+                CAST(SUM(filtered) AS SIGNED), CAST(SUM(failed) AS SIGNED)
+           FROM poll_source_stats WHERE polled_at >= ? GROUP BY repo, reason",
+      )
+      .bind(since)
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("aggregate poll stats per repo and reason in mysql")?;
+
+    Ok(PollStatsSummary {
+      polls,
+      totals: PollStats {
+        fetched,
+        forwarded,
+        filtered,
+        failed,
+        sources: sources_from_rows(sources),
+      },
+    })
+  }
+
+  async fn hold_pending(
+    &self,
+    id: &str,
+    payload: &str,
+    html_url: Option<&str>,
+    chat_id: Option<&str>,
+  ) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO pending_group (id, payload, html_url, chat_id, held_at)
+       VALUES (?, ?, ?, ?, ?)
+       ON DUPLICATE KEY UPDATE payload = VALUES(payload),
+         html_url = VALUES(html_url), chat_id = VALUES(chat_id)",
+      )
+      .bind(id)
+      .bind(payload)
+      .bind(html_url)
+      .bind(chat_id)
+      .bind(Utc::now().timestamp())
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("hold pending notification in mysql: {id}"))?;
+
+    Ok(())
+  }
+
+  async fn list_pending(&self) -> Result<Vec<PendingNotification>> {
+    let rows = retry_db(self.limits, || {
+      sqlx::query_as::<_, (String, String, Option<String>, Option<String>, i64)>(
+        "SELECT id, payload, html_url, chat_id, held_at
+         FROM pending_group ORDER BY held_at, id",
+      )
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list pending notifications in mysql")?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(
+          |(id, payload, html_url, chat_id, held_at)| PendingNotification {
+            id,
+            payload,
+            html_url,
+            chat_id,
+            held_at,
+          },
+        )
+        .collect(),
+    )
+  }
+
+  async fn remove_pending(&self, id: &str) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM pending_group WHERE id = ?")
+        .bind(id)
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("remove pending notification in mysql: {id}"))?;
+
+    Ok(())
+  }
+
+  async fn get_meta(&self, key: &str) -> Result<Option<String>> {
+    retry_db(self.limits, || {
+      sqlx::query_scalar::<_, String>("SELECT value FROM meta WHERE `key` = ?")
+        .bind(key)
+        .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("read meta key in mysql: {key}"))
+  }
+
+  async fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO meta (`key`, value) VALUES (?, ?)
+         ON DUPLICATE KEY UPDATE value = VALUES(value)",
+      )
+      .bind(key)
+      .bind(value)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("write meta key in mysql: {key}"))?;
+
+    Ok(())
+  }
+
+  async fn upsert_escalation(&self, escalation: &Escalation) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO escalations
+           (account, thread_id, payload, html_url, first_sent_at, level, next_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON DUPLICATE KEY UPDATE
+           payload = VALUES(payload),
+           html_url = VALUES(html_url),
+           first_sent_at = VALUES(first_sent_at),
+           level = VALUES(level),
+           next_at = VALUES(next_at)",
+      )
+      .bind(&escalation.account)
+      .bind(&escalation.thread_id)
+      .bind(&escalation.payload)
+      .bind(&escalation.html_url)
+      .bind(escalation.first_sent_at)
+      .bind(escalation.level)
+      .bind(escalation.next_at)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("upsert escalation in mysql: {}", escalation.thread_id))?;
+
+    Ok(())
+  }
+
+  async fn due_escalations(&self, now: i64) -> Result<Vec<Escalation>> {
+    let rows = retry_db(self.limits, || {
+      sqlx::query_as::<_, EscalationRow>(
+        "SELECT account, thread_id, payload, html_url, first_sent_at, level, next_at
+         FROM escalations WHERE next_at <= ? ORDER BY next_at",
+      )
+      .bind(now)
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list due escalations in mysql")?;
+
+    Ok(rows.into_iter().map(escalation_from_row).collect())
+  }
+
+  async fn remove_escalation(&self, account: &str, thread_id: &str) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM escalations WHERE account = ? AND thread_id = ?")
+        .bind(account)
+        .bind(thread_id)
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("remove escalation in mysql: {thread_id}"))?;
+
+    Ok(())
+  }
+
+  async fn record_feed_entry(&self, entry: &FeedEntry, keep: i64) -> Result<()> {
+    retry_db(self.limits, || sqlx::query(
+      "INSERT INTO feed_entries (id, repo, title, html_url, forwarded_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&entry.id)
+    .bind(&entry.repo)
+    .bind(&entry.title)
+    .bind(&entry.html_url)
+    .bind(entry.forwarded_at)
+    .execute(&self.pool))
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("record feed entry in mysql: {}", entry.id))?;
+
+    retry_db(self.limits, || {
+      sqlx::query(
+        "DELETE FROM feed_entries WHERE seq <= (
+           SELECT seq FROM (
+             SELECT seq FROM feed_entries ORDER BY seq DESC LIMIT 1 OFFSET ?
+           ) AS cutoff
+         )",
+      )
+      .bind(keep)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("prune feed entries in mysql")?;
+
+    Ok(())
+  }
+
+  async fn recent_feed_entries(&self, limit: i64) -> Result<Vec<FeedEntry>> {
+    let rows = retry_db(self.limits, || {
+      sqlx::query_as::<_, FeedEntryRow>(
+        "SELECT id, repo, title, html_url, forwarded_at
+         FROM feed_entries ORDER BY seq DESC LIMIT ?",
+      )
+      .bind(limit)
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list feed entries in mysql")?;
+
+    Ok(rows.into_iter().map(feed_entry_from_row).collect())
+  }
+
+  // Clears dedupe state plus the etag, cursors and cooldowns tied to it, so
+  // the next poll forwards everything again. Returns the number of sent rows
+  // removed.
+  async fn reset(&self) -> Result<u64> {
+    let removed = retry_db(self.limits, || {
+      sqlx::query("DELETE FROM sent_notifications").execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("clear sent notifications in mysql")?
+    .rows_affected();
+
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM meta WHERE `key` = ? OR key LIKE 'cooldown:%'")
+        .bind(META_NOTIFICATIONS_ETAG)
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("clear dedupe meta in mysql")?;
+
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM poll_state").execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("clear poll cursors in mysql")?;
+
+    Ok(removed)
+  }
+
+  async fn mute_repo(&self, repo: &str, until: i64) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO muted_repos (repo, until) VALUES (?, ?)
+         ON DUPLICATE KEY UPDATE until = VALUES(until)",
+      )
+      .bind(repo)
+      .bind(until)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("mute repo in mysql: {repo}"))?;
+
+    Ok(())
+  }
+
+  // Expired mutes are dropped on read.
+  async fn active_mutes(&self, now: i64) -> Result<Vec<(String, i64)>> {
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM muted_repos WHERE until <= ?")
+        .bind(now)
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("clear expired mutes in mysql")?;
+
+    retry_db(self.limits, || {
+      sqlx::query_as::<_, (String, i64)>("SELECT repo, until FROM muted_repos")
+        .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list muted repos in mysql")
+  }
+
+  async fn read_state(&self, thread_id: &str) -> Result<Option<bool>> {
+    retry_db(self.limits, || {
+      sqlx::query_scalar::<_, bool>(
+        "SELECT unread FROM thread_read_state WHERE thread_id = ?",
+      )
+      .bind(thread_id)
+      .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("load read state in mysql: {thread_id}"))
+  }
+
+  async fn set_read_state(&self, thread_id: &str, unread: bool) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO thread_read_state (thread_id, unread) VALUES (?, ?)
+         ON DUPLICATE KEY UPDATE unread = VALUES(unread)",
+      )
+      .bind(thread_id)
+      .bind(unread)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("store read state in mysql: {thread_id}"))?;
+
+    Ok(())
+  }
+
+  async fn audit(&self, entry: &AuditEntry) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO audit_log (id, decision, detail, recorded_at)
+         VALUES (?, ?, ?, ?)",
+      )
+      .bind(&entry.id)
+      .bind(&entry.decision)
+      .bind(&entry.detail)
+      .bind(entry.recorded_at)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("record audit entry in mysql: {}", entry.id))?;
+
+    Ok(())
+  }
+
+  async fn audit_since(&self, since: i64) -> Result<Vec<AuditEntry>> {
+    let rows = retry_db(self.limits, || {
+      sqlx::query_as::<_, AuditRow>(
+        "SELECT id, decision, detail, recorded_at
+         FROM audit_log WHERE recorded_at >= ? ORDER BY seq",
+      )
+      .bind(since)
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list audit entries in mysql")?;
+
+    Ok(rows.into_iter().map(audit_entry_from_row).collect())
+  }
+
+  async fn load_cursor(&self, account: &str) -> Result<Option<DateTime<Utc>>> {
+    let cursor = retry_db(self.limits, || {
+      sqlx::query_scalar::<_, i64>(
+        "SELECT since_cursor FROM poll_state WHERE account = ?",
+      )
+      .bind(account)
+      .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("load poll cursor in mysql")?;
+
+    Ok(cursor.and_then(|ts| DateTime::from_timestamp(ts, 0)))
+  }
+
+  async fn save_cursor(&self, account: &str, ts: DateTime<Utc>) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO poll_state (account, since_cursor) VALUES (?, ?)
+         ON DUPLICATE KEY UPDATE since_cursor = VALUES(since_cursor)",
+      )
+      .bind(account)
+      .bind(ts.timestamp())
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("save poll cursor in mysql")?;
+
+    Ok(())
+  }
+
+  async fn repo_topic(&self, chat_id: &str, repo: &str) -> Result<Option<i64>> {
+    retry_db(self.limits, || {
+      sqlx::query_scalar::<_, i64>(
+        "SELECT topic_id FROM repo_topics WHERE chat_id = ? AND repo = ?",
+      )
+      .bind(chat_id)
+      .bind(repo)
+      .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("load repo topic in mysql: {repo}"))
+  }
+
+  async fn set_repo_topic(
+    &self,
+    chat_id: &str,
+    repo: &str,
+    topic_id: i64,
+  ) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO repo_topics (chat_id, repo, topic_id) VALUES (?, ?, ?)
+         ON DUPLICATE KEY UPDATE topic_id = VALUES(topic_id)",
+      )
+      .bind(chat_id)
+      .bind(repo)
+      .bind(topic_id)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("store repo topic in mysql: {repo}"))?;
+
+    Ok(())
+  }
+
+  async fn upsert_retry(&self, item: &RetryItem) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO retry_queue
+           (id, payload, html_url, chat_id, topic_id, attempts, next_at, dead)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+         ON DUPLICATE KEY UPDATE
+           payload = VALUES(payload),
+           html_url = VALUES(html_url),
+           chat_id = VALUES(chat_id),
+           topic_id = VALUES(topic_id),
+           attempts = VALUES(attempts),
+           next_at = VALUES(next_at),
+           dead = VALUES(dead)",
+      )
+      .bind(&item.id)
+      .bind(&item.payload)
+      .bind(&item.html_url)
+      .bind(&item.chat_id)
+      .bind(item.topic_id)
+      .bind(item.attempts)
+      .bind(item.next_at)
+      .bind(item.dead)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("upsert retry item in mysql: {}", item.id))?;
+
+    Ok(())
+  }
+
+  async fn due_retries(&self, now: i64) -> Result<Vec<RetryItem>> {
+    let rows = retry_db(self.limits, || {
+      sqlx::query_as::<_, RetryRow>(
+        "SELECT id, payload, html_url, chat_id, topic_id, attempts, next_at, dead
+         FROM retry_queue WHERE NOT dead AND next_at <= ? ORDER BY next_at",
+      )
+      .bind(now)
+      .fetch_all(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("list due retries in mysql")?;
+
+    Ok(rows.into_iter().map(retry_item_from_row).collect())
+  }
+
+  async fn remove_retry(&self, id: &str) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query("DELETE FROM retry_queue WHERE id = ?")
+        .bind(id)
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("remove retry item in mysql: {id}"))?;
+
+    Ok(())
+  }
+
+  async fn prune(&self, older_than: DateTime<Utc>) -> Result<u64> {
+    let result = retry_db(self.limits, || {
+      sqlx::query("DELETE FROM sent_notifications WHERE sent_at < ?")
+        .bind(older_than)
+        .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("prune sent notifications in mysql")?;
+
+    Ok(result.rows_affected())
+  }
+
+  async fn sent_stats(&self, since: DateTime<Utc>) -> Result<SentStats> {
+    let (total, since) = retry_db(self.limits, || {
+      sqlx::query_as::<_, (i64, i64)>(
+        "SELECT COUNT(*), CAST(COALESCE(SUM(CASE WHEN sent_at >= ? THEN 1 ELSE 0 END), 0) AS SIGNED)
+         FROM sent_notifications",
+      )
+      .bind(since)
+      .fetch_one(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .context("count sent notifications in mysql")?;
+
+    Ok(SentStats { total, since })
+  }
+
+  async fn sent_message(&self, thread_id: &str) -> Result<Option<(String, i64)>> {
+    retry_db(self.limits, || {
+      sqlx::query_as::<_, (String, i64)>(
+        "SELECT chat_id, message_id FROM sent_messages WHERE thread_id = ?",
+      )
+      .bind(thread_id)
+      .fetch_optional(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("load sent message in mysql: {thread_id}"))
+  }
+
+  async fn set_sent_message(
+    &self,
+    thread_id: &str,
+    chat_id: &str,
+    message_id: i64,
+  ) -> Result<()> {
+    retry_db(self.limits, || {
+      sqlx::query(
+        "INSERT INTO sent_messages (thread_id, chat_id, message_id)
+         VALUES (?, ?, ?)
+         ON DUPLICATE KEY UPDATE
+           chat_id = VALUES(chat_id), message_id = VALUES(message_id)",
+      )
+      .bind(thread_id)
+      .bind(chat_id)
+      .bind(message_id)
+      .execute(&self.pool)
+    })
+    .await
+    .map_err(describe_timeout)
+    .with_context(|| format!("store sent message in mysql: {thread_id}"))?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,63 @@
+// Minimal line-based diff (LCS), enough to review formatting changes by eye.
+pub fn line_diff(old: &str, new: &str) -> String {
+  let old: Vec<&str> = old.lines().collect();
+  let new: Vec<&str> = new.lines().collect();
+
+  let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+  for i in (0..old.len()).rev() {
+    for j in (0..new.len()).rev() {
+      lcs[i][j] = if old[i] == new[j] {
+        lcs[i + 1][j + 1] + 1
+      } else {
+        lcs[i + 1][j].max(lcs[i][j + 1])
+      };
+    }
+  }
+
+  let mut output = Vec::new();
+  let (mut i, mut j) = (0, 0);
+  while i < old.len() && j < new.len() {
+    if old[i] == new[j] {
+      output.push(format!("  {}", old[i]));
+      i += 1;
+      j += 1;
+    } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+      output.push(format!("- {}", old[i]));
+      i += 1;
+    } else {
+      output.push(format!("+ {}", new[j]));
+      j += 1;
+    }
+  }
+  output.extend(old[i..].iter().map(|line| format!("- {line}")));
+  output.extend(new[j..].iter().map(|line| format!("+ {line}")));
+
+  output.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn diff_marks_changed_lines_of_a_message() {
+    let old = "🐞 GitHub Notification\nRepo: octo/repo\nTitle: Fix it";
+    let new = "GitHub Notification\nRepo: octo/repo\nTitle: Fix it\n— via prod";
+    assert_eq!(
+      line_diff(old, new),
+      "- 🐞 GitHub Notification\n\
+       + GitHub Notification\n  \
+       Repo: octo/repo\n  \
+       Title: Fix it\n\
+       + — via prod"
+    );
+  }
+
+  #[test]
+  fn identical_messages_have_no_changed_lines() {
+    let text = "Repo: octo/repo\nTitle: Fix it";
+    assert!(line_diff(text, text)
+      .lines()
+      .all(|line| line.starts_with("  ")));
+  }
+}
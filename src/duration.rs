@@ -0,0 +1,47 @@
+use anyhow::{bail, Context, Result};
+use std::time::Duration;
+
+// Parses `90s`, `15m`, `1h` or `7d`; a bare number is taken as seconds.
+pub fn parse_duration(raw: &str) -> Result<Duration> {
+  let raw = raw.trim();
+  let (digits, unit) = match raw.find(|c: char| !c.is_ascii_digit()) {
+    Some(idx) => raw.split_at(idx),
+    None => (raw, "s"),
+  };
+  let value = digits
+    .parse::<u64>()
+    .with_context(|| format!("invalid duration: {raw}"))?;
+  let multiplier = match unit {
+    "s" => 1,
+    "m" => 60,
+    "h" => 60 * 60,
+    "d" => 24 * 60 * 60,
+    _ => bail!("invalid duration unit in {raw}, use s, m, h or d"),
+  };
+  match value.checked_mul(multiplier) {
+    Some(secs) => Ok(Duration::from_secs(secs)),
+    None => bail!("invalid duration: {raw}, too long"),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn units_scale_the_value() {
+    assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+    assert_eq!(parse_duration("15m").unwrap(), Duration::from_secs(900));
+    assert_eq!(parse_duration(" 2h ").unwrap(), Duration::from_secs(7200));
+    assert_eq!(parse_duration("7d").unwrap(), Duration::from_secs(604_800));
+    assert!(parse_duration("3w").is_err());
+    assert!(parse_duration("h").is_err());
+  }
+
+  #[test]
+  fn overflowing_durations_are_rejected() {
+    let err = parse_duration("99999999999999999d").unwrap_err();
+    assert!(err.to_string().contains("too long"));
+    assert!(parse_duration("99999999999999999999").is_err());
+  }
+}
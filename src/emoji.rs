@@ -0,0 +1,213 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+
+const FALLBACK: &str = "🔔";
+
+#[derive(Debug, Clone)]
+pub enum EmojiTheme {
+  Default,
+  Minimal,
+  None,
+  // reason_emoji, with REASON_EMOJI_OVERRIDES on top.
+  Reason(HashMap<String, String>),
+  Custom(HashMap<String, String>),
+}
+
+impl EmojiTheme {
+  pub fn load(
+    theme: &str,
+    mapping_file: Option<&str>,
+    reason_overrides: Option<&str>,
+  ) -> Result<Self> {
+    let theme = theme.to_ascii_lowercase();
+    if reason_overrides.is_some() && theme != "reason" {
+      bail!("REASON_EMOJI_OVERRIDES requires EMOJI_THEME=reason");
+    }
+    match theme.as_str() {
+      "default" => Ok(Self::Default),
+      "minimal" => Ok(Self::Minimal),
+      "none" => Ok(Self::None),
+      "reason" => Ok(Self::Reason(parse_overrides(
+        reason_overrides.unwrap_or(""),
+      )?)),
+      "custom" => {
+        let Some(path) = mapping_file else {
+          bail!("EMOJI_THEME=custom requires EMOJI_THEME_FILE");
+        };
+        let raw = std::fs::read_to_string(path)
+          .with_context(|| format!("read EMOJI_THEME_FILE {path}"))?;
+        Ok(Self::Custom(parse_mapping(&raw)?))
+      }
+      _ => bail!(
+        "invalid EMOJI_THEME: {theme}, use default, minimal, none, reason or custom"
+      ),
+    }
+  }
+
+  pub fn emoji_for(&self, reason: &str, subject_type: &str) -> Option<&str> {
+    match self {
+      Self::Default => Some(type_emoji(subject_type)),
+      Self::Minimal => Some(FALLBACK),
+      Self::None => None,
+      Self::Reason(overrides) => Some(
+        overrides
+          .get(&reason.to_ascii_lowercase())
+          .map(String::as_str)
+          .unwrap_or_else(|| reason_emoji(reason)),
+      ),
+      Self::Custom(mapping) => mapping
+        .get(&reason.to_ascii_lowercase())
+        .or_else(|| mapping.get(&subject_type.to_ascii_lowercase()))
+        .or_else(|| mapping.get("*"))
+        .map(String::as_str)
+        .filter(|emoji| !emoji.is_empty()),
+    }
+  }
+
+  pub fn group_emoji(&self) -> Option<&str> {
+    match self {
+      Self::Default | Self::Minimal | Self::Reason(_) => Some(FALLBACK),
+      Self::None => None,
+      Self::Custom(mapping) => mapping
+        .get("*")
+        .map(String::as_str)
+        .filter(|emoji| !emoji.is_empty()),
+    }
+  }
+}
+
+fn type_emoji(subject_type: &str) -> &'static str {
+  match subject_type {
+    "PullRequest" => "🔀",
+    "Issue" => "🐞",
+    "Release" => "🚀",
+    "Commit" => "📝",
+    "Discussion" => "💬",
+    "CheckSuite" => "⚙️",
+    _ => FALLBACK,
+  }
+}
+
+pub fn reason_emoji(reason: &str) -> &'static str {
+  match reason {
+    "mention" | "team_mention" => "👤",
+    "review_requested" => "👀",
+    "assign" => "📌",
+    "comment" => "💬",
+    "author" => "✍️",
+    "state_change" => "🔄",
+    "security_alert" => "🚨",
+    "ci_activity" => "⚙️",
+    _ => FALLBACK,
+  }
+}
+
+// REASON_EMOJI_OVERRIDES: comma-separated `reason=emoji` pairs.
+fn parse_overrides(raw: &str) -> Result<HashMap<String, String>> {
+  let mut overrides = HashMap::new();
+  for entry in raw
+    .split(',')
+    .map(str::trim)
+    .filter(|entry| !entry.is_empty())
+  {
+    let Some((reason, emoji)) = entry.split_once('=') else {
+      bail!("invalid REASON_EMOJI_OVERRIDES entry: {entry}, expected reason=emoji");
+    };
+    overrides.insert(reason.trim().to_ascii_lowercase(), emoji.trim().to_string());
+  }
+  Ok(overrides)
+}
+
+// One `key=emoji` entry per line, keyed by reason or subject type; `*` is the
+// fallback for everything else. Blank lines and `#` comments are ignored.
+fn parse_mapping(raw: &str) -> Result<HashMap<String, String>> {
+  let mut mapping = HashMap::new();
+
+  for line in raw.lines().map(str::trim) {
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    let Some((key, emoji)) = line.split_once('=') else {
+      bail!("invalid EMOJI_THEME_FILE entry: {line}");
+    };
+    mapping.insert(key.trim().to_ascii_lowercase(), emoji.trim().to_string());
+  }
+
+  Ok(mapping)
+}
+
+pub fn with_emoji(emoji: Option<&str>, text: &str) -> String {
+  match emoji {
+    Some(emoji) => format!("{emoji} {text}"),
+    None => text.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn none_theme_strips_every_emoji() {
+    let theme = EmojiTheme::load("none", None, None).unwrap();
+    assert_eq!(theme.emoji_for("mention", "PullRequest"), None);
+    assert_eq!(theme.group_emoji(), None);
+    assert_eq!(
+      with_emoji(theme.emoji_for("mention", "Issue"), "GitHub Notification"),
+      "GitHub Notification"
+    );
+  }
+
+  #[test]
+  fn custom_theme_prefers_reason_then_type_then_fallback() {
+    let path = std::env::temp_dir().join(format!(
+      "github-notify-to-tg-{}-emoji-theme",
+      std::process::id()
+    ));
+    std::fs::write(
+      &path,
+      "# team theme\nmention = 📣\n\nPullRequest=🧩\n*=•\nrelease=\n",
+    )
+    .unwrap();
+    let theme = EmojiTheme::load("custom", path.to_str(), None).unwrap();
+
+    assert_eq!(theme.emoji_for("mention", "PullRequest"), Some("📣"));
+    assert_eq!(theme.emoji_for("comment", "PullRequest"), Some("🧩"));
+    assert_eq!(theme.emoji_for("comment", "Issue"), Some("•"));
+    assert_eq!(theme.emoji_for("comment", "Release"), None);
+    assert_eq!(theme.group_emoji(), Some("•"));
+  }
+
+  #[test]
+  fn custom_theme_needs_a_valid_mapping_file() {
+    assert!(EmojiTheme::load("custom", None, None).is_err());
+    assert!(parse_mapping("mention 📣").is_err());
+  }
+
+  #[test]
+  fn reason_theme_maps_known_reasons_and_falls_back() {
+    let theme = EmojiTheme::load("reason", None, None).unwrap();
+    assert_eq!(theme.emoji_for("mention", "Issue"), Some("👤"));
+    assert_eq!(
+      theme.emoji_for("review_requested", "PullRequest"),
+      Some("👀")
+    );
+    assert_eq!(
+      theme.emoji_for("security_alert", "RepositoryVulnerabilityAlert"),
+      Some("🚨")
+    );
+    assert_eq!(theme.emoji_for("subscribed", "Issue"), Some(FALLBACK));
+  }
+
+  #[test]
+  fn reason_overrides_replace_the_builtin_mapping() {
+    let theme =
+      EmojiTheme::load("reason", None, Some(" Mention=📣 , subscribed = 👁 ")).unwrap();
+    assert_eq!(theme.emoji_for("mention", "Issue"), Some("📣"));
+    assert_eq!(theme.emoji_for("subscribed", "Issue"), Some("👁"));
+    assert_eq!(theme.emoji_for("comment", "Issue"), Some("💬"));
+
+    assert!(EmojiTheme::load("reason", None, Some("mention")).is_err());
+    assert!(EmojiTheme::load("default", None, Some("mention=📣")).is_err());
+  }
+}
@@ -0,0 +1,350 @@
+use octocrab::models::activity::Notification as GitHubNotification;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+async fn get_json<T: DeserializeOwned>(
+  http: &Client,
+  github_token: &str,
+  api_url: &str,
+) -> Option<T> {
+  let response = http
+    .get(api_url)
+    .bearer_auth(github_token)
+    .header("Accept", "application/vnd.github+json")
+    .send()
+    .await
+    .ok()?;
+
+  if !response.status().is_success() {
+    return None;
+  }
+
+  response.json::<T>().await.ok()
+}
+
+#[derive(Deserialize)]
+struct Login {
+  login: String,
+}
+
+#[derive(Deserialize)]
+struct AuthoredResource {
+  user: Option<Login>,
+  author: Option<Login>,
+}
+
+// Fields of the issue or pull request behind a notification. Pull requests
+// carry no `state_reason`; only they carry the draft, mergeability and file
+// count fields.
+#[derive(Debug, Deserialize)]
+pub struct SubjectDetails {
+  pub html_url: Option<String>,
+  pub state: Option<String>,
+  pub state_reason: Option<String>,
+  #[serde(default)]
+  pub labels: Vec<SubjectLabel>,
+  pub draft: Option<bool>,
+  pub mergeable_state: Option<String>,
+  pub changed_files: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubjectLabel {
+  pub name: String,
+}
+
+impl SubjectDetails {
+  pub fn label_names(&self) -> Vec<String> {
+    self.labels.iter().map(|label| label.name.clone()).collect()
+  }
+}
+
+pub async fn resolve_subject(
+  http: &Client,
+  github_token: &str,
+  api_url: &str,
+) -> Option<SubjectDetails> {
+  get_json::<SubjectDetails>(http, github_token, api_url).await
+}
+
+pub async fn resolve_author(
+  http: &Client,
+  github_token: &str,
+  n: &GitHubNotification,
+) -> Option<String> {
+  let api_url = n
+    .subject
+    .latest_comment_url
+    .as_ref()
+    .or(n.subject.url.as_ref())?;
+
+  let payload =
+    get_json::<AuthoredResource>(http, github_token, api_url.as_str()).await?;
+  payload.user.or(payload.author).map(|login| login.login)
+}
+
+#[derive(Deserialize)]
+struct Comment {
+  body: Option<String>,
+}
+
+// GITHUB_INCLUDE_COMMENT: the body of the comment behind a `mention` or
+// `comment` notification.
+pub async fn resolve_comment_body(
+  http: &Client,
+  github_token: &str,
+  n: &GitHubNotification,
+) -> Option<String> {
+  let api_url = n.subject.latest_comment_url.as_ref()?;
+  let comment = get_json::<Comment>(http, github_token, api_url.as_str()).await?;
+  comment.body.filter(|body| !body.trim().is_empty())
+}
+
+pub fn is_self_authored(author: Option<&str>, self_login: &str) -> bool {
+  match author {
+    Some(author) => author.eq_ignore_ascii_case(self_login),
+    None => false,
+  }
+}
+
+#[derive(Deserialize)]
+struct CheckSuite {
+  conclusion: Option<String>,
+}
+
+pub async fn resolve_check_conclusion(
+  http: &Client,
+  github_token: &str,
+  n: &GitHubNotification,
+) -> Option<String> {
+  if let Some(api_url) = &n.subject.url {
+    let suite = get_json::<CheckSuite>(http, github_token, api_url.as_str()).await;
+    if let Some(conclusion) = suite.and_then(|suite| suite.conclusion) {
+      return Some(conclusion);
+    }
+  }
+
+  conclusion_from_title(&n.subject.title).map(str::to_string)
+}
+
+// CheckSuite notifications usually carry no subject url, only a title such as
+// "CI workflow run failed for main branch".
+fn conclusion_from_title(title: &str) -> Option<&'static str> {
+  let title = title.to_ascii_lowercase();
+  if title.contains(" failed") {
+    Some("failure")
+  } else if title.contains(" succeeded") {
+    Some("success")
+  } else if title.contains(" cancelled") {
+    Some("cancelled")
+  } else {
+    None
+  }
+}
+
+const MISSING_REPO_TTL: Duration = Duration::from_secs(600);
+const REPO_META_TTL: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RepoMeta {
+  pub language: Option<String>,
+  pub stargazers_count: u64,
+  #[serde(default)]
+  pub topics: Vec<String>,
+  #[serde(default)]
+  pub private: bool,
+}
+
+#[derive(Clone)]
+enum RepoLookup {
+  Found(RepoMeta),
+  // Deleted, or transferred away from the token's reach.
+  Missing,
+}
+
+// Repository lookups keyed by API url. A 404 is only remembered briefly, while
+// metadata such as language and stars changes slowly and is kept longer.
+#[derive(Default)]
+pub struct RepoCache {
+  entries: Mutex<HashMap<String, (RepoLookup, Instant)>>,
+}
+
+impl RepoCache {
+  pub async fn is_deleted(
+    &self,
+    http: &Client,
+    github_token: &str,
+    n: &GitHubNotification,
+  ) -> bool {
+    matches!(
+      self.lookup(http, github_token, n).await,
+      Some(RepoLookup::Missing)
+    )
+  }
+
+  pub async fn repo_meta(
+    &self,
+    http: &Client,
+    github_token: &str,
+    n: &GitHubNotification,
+  ) -> Option<RepoMeta> {
+    match self.lookup(http, github_token, n).await? {
+      RepoLookup::Found(meta) => Some(meta),
+      RepoLookup::Missing => None,
+    }
+  }
+
+  async fn lookup(
+    &self,
+    http: &Client,
+    github_token: &str,
+    n: &GitHubNotification,
+  ) -> Option<RepoLookup> {
+    let api_url = n.repository.url.as_str();
+    if let Some(cached) = self.cached(api_url) {
+      return Some(cached);
+    }
+
+    let response = http
+      .get(api_url)
+      .bearer_auth(github_token)
+      .header("Accept", "application/vnd.github+json")
+      .send()
+      .await
+      .ok()?;
+    let lookup = if response.status() == reqwest::StatusCode::NOT_FOUND {
+      RepoLookup::Missing
+    } else if response.status().is_success() {
+      RepoLookup::Found(response.json::<RepoMeta>().await.ok()?)
+    } else {
+      return None;
+    };
+
+    if let Ok(mut entries) = self.entries.lock() {
+      entries.insert(api_url.to_string(), (lookup.clone(), Instant::now()));
+    }
+    Some(lookup)
+  }
+
+  fn cached(&self, api_url: &str) -> Option<RepoLookup> {
+    let entries = self.entries.lock().ok()?;
+    let (lookup, checked_at) = entries.get(api_url)?;
+    let ttl = match lookup {
+      RepoLookup::Found(_) => REPO_META_TTL,
+      RepoLookup::Missing => MISSING_REPO_TTL,
+    };
+    (checked_at.elapsed() < ttl).then(|| lookup.clone())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  use std::sync::Arc;
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+  use tokio::net::TcpListener;
+
+  // Answers every request with `status` and `body`, counting the requests.
+  async fn github_server(status: &str, body: &str) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let requests = Arc::new(AtomicUsize::new(0));
+    let response = format!(
+      "HTTP/1.1 {status}\r\ncontent-type: application/json\r\n\
+       content-length: {}\r\nconnection: close\r\n\r\n{body}",
+      body.len()
+    );
+    let counter = requests.clone();
+    tokio::spawn(async move {
+      while let Ok((mut stream, _)) = listener.accept().await {
+        let mut request = vec![0; 8192];
+        let _ = stream.read(&mut request).await;
+        counter.fetch_add(1, Ordering::SeqCst);
+        let _ = stream.write_all(response.as_bytes()).await;
+      }
+    });
+    (format!("http://{addr}"), requests)
+  }
+
+  fn notification(repo_url: &str) -> GitHubNotification {
+    serde_json::from_value(json!({
+      "id": "1",
+      "repository": {
+        "id": 1,
+        "name": "repo",
+        "full_name": "octo/repo",
+        "url": format!("{repo_url}/repos/octo/repo"),
+      },
+      "subject": {
+        "title": "Title",
+        "url": null,
+        "latest_comment_url": null,
+        "type": "Issue",
+      },
+      "reason": "mention",
+      "unread": true,
+      "updated_at": "2024-01-01T00:00:00Z",
+      "last_read_at": null,
+      "url": "https://api.github.com/notifications/threads/1",
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn own_activity_is_recognised_case_insensitively() {
+    assert!(is_self_authored(Some("octocat"), "octocat"));
+    assert!(is_self_authored(Some("OctoCat"), "octocat"));
+    assert!(!is_self_authored(Some("hubot"), "octocat"));
+  }
+
+  #[test]
+  fn unresolved_author_is_never_suppressed() {
+    assert!(!is_self_authored(None, "octocat"));
+  }
+
+  #[test]
+  fn check_suite_titles_carry_the_conclusion() {
+    let conclusion = conclusion_from_title;
+    assert_eq!(
+      conclusion("CI workflow run failed for main branch"),
+      Some("failure")
+    );
+    assert_eq!(conclusion("Build succeeded for v1.2"), Some("success"));
+    assert_eq!(
+      conclusion("Deploy cancelled for main branch"),
+      Some("cancelled")
+    );
+    assert_eq!(conclusion("Checks pending"), None);
+  }
+
+  #[tokio::test]
+  async fn missing_repo_is_deleted_and_cached() {
+    let (api_url, requests) = github_server("404 Not Found", "{}").await;
+    let cache = RepoCache::default();
+    let n = notification(&api_url);
+    let http = Client::new();
+
+    assert!(cache.is_deleted(&http, "token", &n).await);
+    assert!(cache.is_deleted(&http, "token", &n).await);
+    assert!(cache.repo_meta(&http, "token", &n).await.is_none());
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+  }
+
+  #[tokio::test]
+  async fn lookup_errors_are_neither_deleted_nor_cached() {
+    let (api_url, requests) = github_server("500 Internal Server Error", "{}").await;
+    let cache = RepoCache::default();
+    let n = notification(&api_url);
+    let http = Client::new();
+
+    assert!(!cache.is_deleted(&http, "token", &n).await);
+    assert!(!cache.is_deleted(&http, "token", &n).await);
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+  }
+}
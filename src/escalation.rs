@@ -0,0 +1,83 @@
+use crate::duration::parse_duration;
+use crate::rules::Rule;
+use anyhow::{bail, Result};
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct EscalationConfig {
+  pub rules: Vec<Rule>,
+  // Offsets from the first send at which an unread notification is re-sent.
+  pub schedule: Vec<Duration>,
+}
+
+pub fn parse_schedule(raw: &str) -> Result<Vec<Duration>> {
+  let schedule = raw
+    .split(',')
+    .map(str::trim)
+    .filter(|step| !step.is_empty())
+    .map(parse_duration)
+    .collect::<Result<Vec<_>>>()?;
+
+  if schedule.is_empty() {
+    bail!("ESCALATE_SCHEDULE must list at least one duration");
+  }
+  if schedule.windows(2).any(|pair| pair[0] >= pair[1]) {
+    bail!("ESCALATE_SCHEDULE durations must be strictly increasing");
+  }
+
+  Ok(schedule)
+}
+
+// Returns when the escalation at `level` (0-based) is due, or `None` once the
+// schedule is exhausted.
+pub fn next_escalation_at(
+  first_sent_at: i64,
+  schedule: &[Duration],
+  level: usize,
+) -> Option<i64> {
+  schedule
+    .get(level)
+    .map(|offset| first_sent_at + offset.as_secs() as i64)
+}
+
+pub fn escalation_header(level: usize, total: usize) -> String {
+  let emoji = match level {
+    0 => "❗",
+    1 => "‼️",
+    _ => "🚨",
+  };
+  format!("{emoji} Still unread (escalation {}/{total})", level + 1)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn schedule_must_be_strictly_increasing() {
+    assert_eq!(
+      parse_schedule("30m, 2h,6h").unwrap(),
+      [1800, 7200, 21600].map(Duration::from_secs)
+    );
+    assert!(parse_schedule("").is_err());
+    assert!(parse_schedule("2h,30m").is_err());
+    assert!(parse_schedule("1h,1h").is_err());
+  }
+
+  #[test]
+  fn escalations_follow_the_schedule_then_stop() {
+    let schedule = parse_schedule("30m,2h").unwrap();
+    let first_sent_at = 1_000;
+    let due: Vec<_> = (0..3)
+      .map(|level| next_escalation_at(first_sent_at, &schedule, level))
+      .collect();
+    assert_eq!(due, [Some(2_800), Some(8_200), None]);
+  }
+
+  #[test]
+  fn header_grows_louder_with_each_level() {
+    assert_eq!(escalation_header(0, 3), "❗ Still unread (escalation 1/3)");
+    assert_eq!(escalation_header(1, 3), "‼️ Still unread (escalation 2/3)");
+    assert_eq!(escalation_header(2, 3), "🚨 Still unread (escalation 3/3)");
+  }
+}
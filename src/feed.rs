@@ -0,0 +1,101 @@
+use crate::db::FeedEntry;
+use chrono::{DateTime, Utc};
+use html_escape::{encode_double_quoted_attribute, encode_text};
+
+pub const FEED_ENTRY_LIMIT: i64 = 50;
+
+fn atom_timestamp(ts: i64) -> String {
+  DateTime::<Utc>::from_timestamp(ts, 0)
+    .unwrap_or_default()
+    .to_rfc3339()
+}
+
+// Entries are expected newest first, as returned by recent_feed_entries.
+pub fn atom_feed(entries: &[FeedEntry]) -> String {
+  let updated = entries
+    .first()
+    .map(|entry| entry.forwarded_at)
+    .unwrap_or_else(|| Utc::now().timestamp());
+
+  let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+  out.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+  out.push_str("  <title>GitHub Notifications</title>\n");
+  out.push_str("  <id>urn:github-notify-to-tg:feed</id>\n");
+  out.push_str(&format!(
+    "  <updated>{}</updated>\n",
+    atom_timestamp(updated)
+  ));
+
+  for entry in entries {
+    out.push_str("  <entry>\n");
+    out.push_str(&format!(
+      "    <title>{}: {}</title>\n",
+      encode_text(&entry.repo),
+      encode_text(&entry.title)
+    ));
+    out.push_str(&format!(
+      "    <id>urn:github-notify-to-tg:{}</id>\n",
+      encode_text(&entry.id)
+    ));
+    if let Some(url) = &entry.html_url {
+      out.push_str(&format!(
+        "    <link href=\"{}\"/>\n",
+        encode_double_quoted_attribute(url)
+      ));
+    }
+    out.push_str(&format!(
+      "    <updated>{}</updated>\n",
+      atom_timestamp(entry.forwarded_at)
+    ));
+    out.push_str("  </entry>\n");
+  }
+
+  out.push_str("</feed>\n");
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn feed_lists_entries_with_escaped_text() {
+    let entries = [
+      FeedEntry {
+        id: "2".to_string(),
+        repo: "octo/repo".to_string(),
+        title: "Fix <script> & \"quotes\"".to_string(),
+        html_url: Some("https://github.com/octo/repo/pull/2?a=1&b=2".to_string()),
+        forwarded_at: 1_704_067_260,
+      },
+      FeedEntry {
+        id: "1".to_string(),
+        repo: "octo/repo".to_string(),
+        title: "Release v1".to_string(),
+        html_url: None,
+        forwarded_at: 1_704_067_200,
+      },
+    ];
+    assert_eq!(
+      atom_feed(&entries),
+      r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>GitHub Notifications</title>
+  <id>urn:github-notify-to-tg:feed</id>
+  <updated>2024-01-01T00:01:00+00:00</updated>
+  <entry>
+    <title>octo/repo: Fix &lt;script&gt; &amp; "quotes"</title>
+    <id>urn:github-notify-to-tg:2</id>
+    <link href="https://github.com/octo/repo/pull/2?a=1&amp;b=2"/>
+    <updated>2024-01-01T00:01:00+00:00</updated>
+  </entry>
+  <entry>
+    <title>octo/repo: Release v1</title>
+    <id>urn:github-notify-to-tg:1</id>
+    <updated>2024-01-01T00:00:00+00:00</updated>
+  </entry>
+</feed>
+"#
+    );
+  }
+}
@@ -0,0 +1,560 @@
+use crate::duration::parse_duration;
+use crate::rules::matches_repo_pattern;
+use anyhow::{bail, Result};
+use octocrab::models::activity::Notification as GitHubNotification;
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub const CI_ACTIVITY_REASON: &str = "ci_activity";
+
+pub fn is_ci_activity(n: &GitHubNotification) -> bool {
+  n.reason.eq_ignore_ascii_case(CI_ACTIVITY_REASON)
+}
+
+// Unknown conclusions are treated as failures so a possibly broken build is
+// never dropped just because the enrichment came back empty.
+pub fn is_failed_conclusion(conclusion: Option<&str>) -> bool {
+  !matches!(
+    conclusion,
+    Some("success" | "neutral" | "skipped" | "cancelled" | "stale")
+  )
+}
+
+// The owner is a user or an organization alike; both are matched by login.
+pub fn repo_owner(n: &GitHubNotification) -> Option<&str> {
+  n.repository
+    .owner
+    .as_ref()
+    .map(|owner| owner.login.as_str())
+    .or_else(|| {
+      n.repository
+        .full_name
+        .as_deref()
+        .and_then(|name| name.split_once('/'))
+        .map(|(owner, _)| owner)
+    })
+}
+
+pub fn owner_allowed(allowlist: &[String], n: &GitHubNotification) -> bool {
+  if allowlist.is_empty() {
+    return true;
+  }
+  repo_owner(n).is_some_and(|owner| {
+    allowlist
+      .iter()
+      .any(|allowed| allowed.eq_ignore_ascii_case(owner))
+  })
+}
+
+// The denylist wins over the allowlist; an empty allowlist allows every repo.
+pub fn repo_allowed(
+  allowlist: &[String],
+  denylist: &[String],
+  n: &GitHubNotification,
+) -> bool {
+  let repo = n.repository.full_name.as_deref().unwrap_or_default();
+  if denylist
+    .iter()
+    .any(|pattern| matches_repo_pattern(pattern, repo))
+  {
+    return false;
+  }
+  allowlist.is_empty()
+    || allowlist
+      .iter()
+      .any(|pattern| matches_repo_pattern(pattern, repo))
+}
+
+pub const KNOWN_SUBJECT_TYPES: &[&str] = &[
+  "Issue",
+  "PullRequest",
+  "Release",
+  "Commit",
+  "Discussion",
+  "CheckSuite",
+  "RepositoryInvitation",
+  "RepositoryVulnerabilityAlert",
+  "RepositoryDependabotAlertsThread",
+  "SecurityAdvisory",
+];
+
+pub fn is_known_subject_type(subject_type: &str) -> bool {
+  KNOWN_SUBJECT_TYPES.contains(&subject_type)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownTypePolicy {
+  Forward,
+  Skip,
+  ForwardTagged,
+}
+
+impl UnknownTypePolicy {
+  pub fn parse(raw: &str) -> Result<Self> {
+    match raw.to_ascii_lowercase().as_str() {
+      "forward" => Ok(Self::Forward),
+      "skip" => Ok(Self::Skip),
+      "forward_tagged" => Ok(Self::ForwardTagged),
+      _ => {
+        bail!("invalid UNKNOWN_TYPE_POLICY: {raw}, use forward, skip or forward_tagged")
+      }
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisibilityFilter {
+  All,
+  Private,
+  Public,
+}
+
+impl VisibilityFilter {
+  pub fn parse(raw: &str) -> Result<Self> {
+    match raw.to_ascii_lowercase().as_str() {
+      "all" => Ok(Self::All),
+      "private" => Ok(Self::Private),
+      "public" => Ok(Self::Public),
+      _ => bail!("invalid VISIBILITY_FILTER: {raw}, use private, public or all"),
+    }
+  }
+
+  pub fn allows(self, private: bool) -> bool {
+    match self {
+      Self::All => true,
+      Self::Private => private,
+      Self::Public => !private,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReasonCooldowns(HashMap<String, Duration>);
+
+impl ReasonCooldowns {
+  // `reason=duration` pairs, e.g. `subscribed=1h,team_mention=15m`.
+  pub fn parse(raw: &str) -> Result<Self> {
+    let mut cooldowns = HashMap::new();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+      let Some((reason, interval)) = entry.split_once('=') else {
+        bail!("invalid REASON_COOLDOWNS entry: {entry}");
+      };
+      cooldowns.insert(
+        reason.trim().to_ascii_lowercase(),
+        parse_duration(interval)?,
+      );
+    }
+    Ok(Self(cooldowns))
+  }
+
+  pub fn interval_for(&self, reason: &str) -> Option<Duration> {
+    self.0.get(&reason.to_ascii_lowercase()).copied()
+  }
+}
+
+pub fn cooldown_key(n: &GitHubNotification) -> String {
+  format!(
+    "cooldown:{}:{}",
+    n.repository.full_name.as_deref().unwrap_or_default(),
+    n.reason.to_ascii_lowercase()
+  )
+}
+
+pub fn in_cooldown(last_forwarded: Option<i64>, now: i64, interval: Duration) -> bool {
+  match last_forwarded {
+    Some(last) => now - last < interval.as_secs() as i64,
+    None => false,
+  }
+}
+
+// Mutes are keyed by repo name or `owner/*`; the store already drops expired
+// entries, the `until` check covers mutes expiring mid-poll.
+pub fn is_muted(mutes: &[(String, i64)], n: &GitHubNotification, now: i64) -> bool {
+  let repo_name = n.repository.full_name.as_deref().unwrap_or_default();
+  mutes
+    .iter()
+    .any(|(pattern, until)| *until > now && matches_repo_pattern(pattern, repo_name))
+}
+
+// A repo without topics never matches a non-empty allowlist.
+pub fn topics_allowed(allowlist: &[String], topics: &[String]) -> bool {
+  allowlist.is_empty()
+    || topics.iter().any(|topic| {
+      allowlist
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(topic))
+    })
+}
+
+// Matched case-insensitively against the notification `reason`; the denylist
+// wins over the allowlist.
+pub fn reason_allowed(
+  allowlist: &[String],
+  denylist: &[String],
+  n: &GitHubNotification,
+) -> bool {
+  let listed = |list: &[String]| {
+    list
+      .iter()
+      .any(|reason| reason.eq_ignore_ascii_case(&n.reason))
+  };
+  !listed(denylist) && (allowlist.is_empty() || listed(allowlist))
+}
+
+// Subjects without a state_reason, such as open issues and pull requests,
+// always pass.
+pub fn state_reason_allowed(allowlist: &[String], state_reason: Option<&str>) -> bool {
+  match state_reason {
+    Some(reason) if !allowlist.is_empty() => allowlist
+      .iter()
+      .any(|allowed| allowed.eq_ignore_ascii_case(reason)),
+    _ => true,
+  }
+}
+
+// Issue or pull request number from a subject url such as
+// `https://api.github.com/repos/owner/repo/issues/123`.
+pub fn subject_number(n: &GitHubNotification) -> Option<u64> {
+  let url = n.subject.url.as_ref()?;
+  let mut segments = url.path_segments()?.rev();
+  let number = segments.next()?.parse().ok()?;
+  matches!(segments.next(), Some("issues" | "pulls")).then_some(number)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NumberThresholds(Vec<(String, u64)>);
+
+impl NumberThresholds {
+  // `repo:>N` or `repo:>=N` entries, e.g. `owner/repo:>1000,owner/*:>=50`.
+  pub fn parse(raw: &str) -> Result<Self> {
+    let mut thresholds = Vec::new();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+      let Some((repo, condition)) = entry.rsplit_once(':') else {
+        bail!("invalid NUMBER_THRESHOLDS entry: {entry}");
+      };
+      let (inclusive, number) = match condition.trim().strip_prefix(">=") {
+        Some(number) => (true, number),
+        None => match condition.trim().strip_prefix('>') {
+          Some(number) => (false, number),
+          None => bail!("invalid NUMBER_THRESHOLDS condition, use >N or >=N: {entry}"),
+        },
+      };
+      let Ok(number) = number.trim().parse::<u64>() else {
+        bail!("invalid NUMBER_THRESHOLDS number: {entry}");
+      };
+      // Stored as the smallest number that passes.
+      let minimum = if inclusive { number } else { number + 1 };
+      thresholds.push((repo.trim().to_string(), minimum));
+    }
+    Ok(Self(thresholds))
+  }
+
+  // Notifications without an issue or PR number, and repos without a
+  // threshold, always pass.
+  pub fn allows(&self, n: &GitHubNotification) -> bool {
+    let Some(number) = subject_number(n) else {
+      return true;
+    };
+    let repo_name = n.repository.full_name.as_deref().unwrap_or_default();
+    self
+      .0
+      .iter()
+      .filter(|(pattern, _)| matches_repo_pattern(pattern, repo_name))
+      .all(|(_, minimum)| number >= *minimum)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn notification(
+    repo: &str,
+    reason: &str,
+    subject_url: Option<&str>,
+  ) -> GitHubNotification {
+    serde_json::from_value(serde_json::json!({
+      "id": "1",
+      "repository": {
+        "id": 1,
+        "name": repo.rsplit('/').next(),
+        "full_name": repo,
+        "url": format!("https://api.github.com/repos/{repo}"),
+      },
+      "subject": {
+        "title": "Title",
+        "url": subject_url,
+        "latest_comment_url": null,
+        "type": "Issue",
+      },
+      "reason": reason,
+      "unread": true,
+      "updated_at": "2024-01-01T00:00:00Z",
+      "last_read_at": null,
+      "url": "https://api.github.com/notifications/threads/1",
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn ci_activity_is_matched_by_reason() {
+    assert!(is_ci_activity(&notification(
+      "octo/repo",
+      "ci_activity",
+      None
+    )));
+    assert!(is_ci_activity(&notification(
+      "octo/repo",
+      "CI_Activity",
+      None
+    )));
+    assert!(!is_ci_activity(&notification("octo/repo", "mention", None)));
+  }
+
+  #[test]
+  fn only_failed_or_unknown_conclusions_count_as_failures() {
+    for conclusion in ["failure", "timed_out", "action_required"] {
+      assert!(is_failed_conclusion(Some(conclusion)), "{conclusion}");
+    }
+    for conclusion in ["success", "neutral", "skipped", "cancelled", "stale"] {
+      assert!(!is_failed_conclusion(Some(conclusion)), "{conclusion}");
+    }
+    assert!(is_failed_conclusion(None));
+  }
+
+  #[test]
+  fn unknown_type_policy_parses_each_value() {
+    assert_eq!(
+      UnknownTypePolicy::parse("Forward").unwrap(),
+      UnknownTypePolicy::Forward
+    );
+    assert_eq!(
+      UnknownTypePolicy::parse("skip").unwrap(),
+      UnknownTypePolicy::Skip
+    );
+    assert_eq!(
+      UnknownTypePolicy::parse("forward_tagged").unwrap(),
+      UnknownTypePolicy::ForwardTagged
+    );
+    assert!(UnknownTypePolicy::parse("drop").is_err());
+    assert!(is_known_subject_type("PullRequest"));
+    assert!(!is_known_subject_type("MergeQueueEntry"));
+  }
+
+  #[test]
+  fn cooldown_lasts_the_configured_interval() {
+    let cooldowns = ReasonCooldowns::parse("subscribed=1h, Team_Mention=15m").unwrap();
+    let hour = cooldowns.interval_for("SUBSCRIBED").unwrap();
+    assert_eq!(hour, Duration::from_secs(3600));
+    assert_eq!(
+      cooldowns.interval_for("team_mention"),
+      Some(Duration::from_secs(900))
+    );
+    assert_eq!(cooldowns.interval_for("mention"), None);
+    assert!(ReasonCooldowns::parse("subscribed").is_err());
+
+    assert!(!in_cooldown(None, 10_000, hour));
+    assert!(in_cooldown(Some(10_000), 13_599, hour));
+    assert!(!in_cooldown(Some(10_000), 13_600, hour));
+  }
+
+  #[test]
+  fn owner_allowlist_matches_the_repo_owner() {
+    let allowlist = ["MyOrg".to_string(), "alice".to_string()];
+    let owned_by = |repo| notification(repo, "mention", None);
+    assert!(owner_allowed(&allowlist, &owned_by("myorg/service")));
+    assert!(owner_allowed(&allowlist, &owned_by("alice/dotfiles")));
+    assert!(!owner_allowed(&allowlist, &owned_by("myorg-fork/service")));
+    assert!(!owner_allowed(&allowlist, &owned_by("bob/myorg")));
+    assert!(owner_allowed(&[], &owned_by("bob/myorg")));
+    assert_eq!(repo_owner(&owned_by("alice/dotfiles")), Some("alice"));
+  }
+
+  #[test]
+  fn mutes_hold_until_they_expire() {
+    let n = notification("octo/repo", "mention", None);
+    let mutes = vec![("octo/repo".to_string(), 200)];
+    assert!(is_muted(&mutes, &n, 100));
+    assert!(is_muted(&mutes, &n, 199));
+    assert!(!is_muted(&mutes, &n, 200));
+    assert!(!is_muted(&[], &n, 100));
+  }
+
+  #[test]
+  fn owner_mutes_cover_every_repo() {
+    let mutes = vec![("octo/*".to_string(), 200)];
+    assert!(is_muted(
+      &mutes,
+      &notification("octo/repo", "mention", None),
+      100
+    ));
+    assert!(!is_muted(
+      &mutes,
+      &notification("other/repo", "mention", None),
+      100
+    ));
+  }
+
+  #[test]
+  fn topics_match_the_allowlist_case_insensitively() {
+    let allowlist = vec!["rust".to_string(), "cli".to_string()];
+    let topics =
+      |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    assert!(topics_allowed(&allowlist, &topics(&["web", "Rust"])));
+    assert!(!topics_allowed(&allowlist, &topics(&["web"])));
+    assert!(!topics_allowed(&allowlist, &[]));
+    assert!(topics_allowed(&[], &[]));
+  }
+
+  #[test]
+  fn subject_numbers_come_from_issue_and_pull_urls() {
+    let number = |url| subject_number(&notification("octo/repo", "mention", Some(url)));
+    assert_eq!(
+      number("https://api.github.com/repos/octo/repo/issues/123"),
+      Some(123)
+    );
+    assert_eq!(
+      number("https://api.github.com/repos/octo/repo/pulls/7"),
+      Some(7)
+    );
+    assert_eq!(
+      number("https://api.github.com/repos/octo/repo/releases/99"),
+      None
+    );
+    assert_eq!(
+      subject_number(&notification("octo/repo", "mention", None)),
+      None
+    );
+  }
+
+  #[test]
+  fn number_thresholds_compare_exclusive_and_inclusive() {
+    let thresholds = NumberThresholds::parse("octo/repo:>1000, octo/*:>=50").unwrap();
+    let issue = |repo, number| {
+      let url = format!("https://api.github.com/repos/{repo}/issues/{number}");
+      notification(repo, "mention", Some(&url))
+    };
+    assert!(!thresholds.allows(&issue("octo/repo", 1000)));
+    assert!(thresholds.allows(&issue("octo/repo", 1001)));
+    assert!(!thresholds.allows(&issue("octo/other", 49)));
+    assert!(thresholds.allows(&issue("octo/other", 50)));
+    assert!(thresholds.allows(&issue("else/repo", 1)));
+    assert!(thresholds.allows(&notification("octo/repo", "mention", None)));
+
+    assert!(NumberThresholds::parse("octo/repo:<5").is_err());
+    assert!(NumberThresholds::parse("octo/repo").is_err());
+  }
+
+  #[test]
+  fn state_reasons_filter_case_insensitively() {
+    let allowlist = vec!["reopened".to_string()];
+    assert!(state_reason_allowed(&allowlist, Some("REOPENED")));
+    assert!(!state_reason_allowed(&allowlist, Some("completed")));
+    assert!(state_reason_allowed(&allowlist, None));
+    assert!(state_reason_allowed(&[], Some("not_planned")));
+  }
+
+  #[test]
+  fn visibility_filter_matches_repo_privacy() {
+    let allows = |raw, private| VisibilityFilter::parse(raw).unwrap().allows(private);
+    assert!(allows("all", true) && allows("all", false));
+    assert!(allows("Private", true) && !allows("private", false));
+    assert!(allows("PUBLIC", false) && !allows("public", true));
+    assert!(VisibilityFilter::parse("internal").is_err());
+  }
+
+  fn list(patterns: &[&str]) -> Vec<String> {
+    patterns.iter().map(|p| p.to_string()).collect()
+  }
+
+  #[test]
+  fn repo_lists_match_exact_names_case_insensitively() {
+    let allowlist = list(&["octo/repo"]);
+    assert!(repo_allowed(
+      &allowlist,
+      &[],
+      &notification("Octo/Repo", "mention", None)
+    ));
+    assert!(!repo_allowed(
+      &allowlist,
+      &[],
+      &notification("octo/other", "mention", None)
+    ));
+    assert!(repo_allowed(
+      &[],
+      &[],
+      &notification("any/repo", "mention", None)
+    ));
+  }
+
+  #[test]
+  fn repo_list_wildcards_cover_a_whole_owner() {
+    let allowlist = list(&["octo/*"]);
+    assert!(repo_allowed(
+      &allowlist,
+      &[],
+      &notification("octo/repo", "mention", None)
+    ));
+    assert!(!repo_allowed(
+      &allowlist,
+      &[],
+      &notification("octopus/repo", "mention", None)
+    ));
+  }
+
+  #[test]
+  fn repo_denylist_wins_over_the_allowlist() {
+    let (allowlist, denylist) = (list(&["octo/*"]), list(&["octo/secret"]));
+    let allowed =
+      |repo| repo_allowed(&allowlist, &denylist, &notification(repo, "mention", None));
+    assert!(allowed("octo/repo"));
+    assert!(!allowed("octo/secret"));
+    assert!(!repo_allowed(
+      &[],
+      &list(&["octo/*"]),
+      &notification("octo/repo", "mention", None)
+    ));
+  }
+
+  #[test]
+  fn reasons_are_kept_or_dropped_by_the_lists() {
+    let allowlist = list(&["mention", "review_requested"]);
+    let denylist = list(&["ci_activity"]);
+    let kept = |reason| {
+      reason_allowed(
+        &allowlist,
+        &denylist,
+        &notification("octo/repo", reason, None),
+      )
+    };
+    assert!(kept("mention"));
+    assert!(kept("REVIEW_REQUESTED"));
+    assert!(!kept("subscribed"));
+    assert!(!kept("ci_activity"));
+  }
+
+  #[test]
+  fn reason_denylist_alone_drops_only_its_reasons() {
+    let denylist = list(&["subscribed", "mention"]);
+    let kept =
+      |reason| reason_allowed(&[], &denylist, &notification("octo/repo", reason, None));
+    for reason in [
+      "assign",
+      "author",
+      "comment",
+      "review_requested",
+      "team_mention",
+    ] {
+      assert!(kept(reason), "{reason}");
+    }
+    assert!(!kept("subscribed"));
+    assert!(!kept("Mention"));
+    // The denylist wins when a reason is on both lists.
+    let both = list(&["mention"]);
+    assert!(!reason_allowed(
+      &both,
+      &both,
+      &notification("octo/repo", "mention", None)
+    ));
+  }
+}
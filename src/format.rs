@@ -0,0 +1,1441 @@
+use crate::emoji::{with_emoji, EmojiTheme};
+use crate::filter::is_known_subject_type;
+use crate::github_url::{api_url_to_html_url, DEFAULT_WEB_URL};
+use crate::template::{MessageTemplate, Placeholder, TemplatePart};
+use anyhow::{anyhow, bail, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use octocrab::models::activity::Notification as GitHubNotification;
+use serde::Serialize;
+
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+  pub format: MessageFormat,
+  pub emoji_theme: EmojiTheme,
+  pub tag_unknown_types: bool,
+  pub hide_fallback_link: bool,
+  // INSTANCE_NAME, footed on every message as `— via <name>`.
+  pub instance_name: Option<String>,
+  // Web host for links built without an html_url, e.g. GitHub Enterprise.
+  pub github_web_url: String,
+  // MESSAGE_TEMPLATE, replacing the default layout of single notifications.
+  pub template: Option<MessageTemplate>,
+  // MAX_TITLE_LENGTH, in characters.
+  pub max_title_chars: Option<usize>,
+  pub time_display: TimeDisplay,
+}
+
+pub const DEFAULT_TIME_FORMAT: &str = "%Y-%m-%d %H:%M %Z";
+
+// DISPLAY_TIMEZONE and TIME_FORMAT for the Updated field.
+#[derive(Debug, Clone)]
+pub struct TimeDisplay {
+  timezone: Tz,
+  format: String,
+}
+
+impl TimeDisplay {
+  pub fn parse(timezone: &str, format: Option<&str>) -> Result<Self> {
+    let timezone = timezone
+      .parse::<Tz>()
+      .map_err(|err| anyhow!("invalid DISPLAY_TIMEZONE: {timezone}: {err}"))?;
+    let format = format.unwrap_or(DEFAULT_TIME_FORMAT);
+    if StrftimeItems::new(format).any(|item| item == Item::Error) {
+      bail!("invalid TIME_FORMAT: {format}, expected a strftime pattern");
+    }
+    Ok(Self {
+      timezone,
+      format: format.to_string(),
+    })
+  }
+
+  pub fn render(&self, ts: DateTime<Utc>) -> String {
+    ts.with_timezone(&self.timezone)
+      .format(&self.format)
+      .to_string()
+  }
+}
+
+impl Default for TimeDisplay {
+  fn default() -> Self {
+    Self {
+      timezone: chrono_tz::UTC,
+      format: DEFAULT_TIME_FORMAT.to_string(),
+    }
+  }
+}
+
+impl Default for FormatOptions {
+  fn default() -> Self {
+    Self {
+      format: MessageFormat::TelegramHtml,
+      emoji_theme: EmojiTheme::Default,
+      tag_unknown_types: false,
+      hide_fallback_link: false,
+      instance_name: None,
+      github_web_url: DEFAULT_WEB_URL.to_string(),
+      template: None,
+      max_title_chars: None,
+      time_display: TimeDisplay::default(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageFormat {
+  TelegramHtml,
+  MarkdownV2,
+  PlainText,
+  Entities,
+}
+
+impl MessageFormat {
+  pub fn parse(raw: &str) -> Result<Self> {
+    match raw.to_ascii_lowercase().as_str() {
+      "html" => Ok(Self::TelegramHtml),
+      "markdownv2" => Ok(Self::MarkdownV2),
+      "plain" | "text" => Ok(Self::PlainText),
+      "entities" => Ok(Self::Entities),
+      _ => {
+        bail!("invalid MESSAGE_FORMAT: {raw}, use html, markdownv2, plain or entities")
+      }
+    }
+  }
+
+  // TELEGRAM_PARSE_MODE spelling, as in the Bot API `parse_mode` field.
+  pub fn from_parse_mode(raw: &str) -> Result<Self> {
+    match raw.to_ascii_lowercase().as_str() {
+      "html" => Ok(Self::TelegramHtml),
+      "markdownv2" => Ok(Self::MarkdownV2),
+      "none" => Ok(Self::PlainText),
+      _ => bail!("invalid TELEGRAM_PARSE_MODE: {raw}, use HTML, MarkdownV2 or none"),
+    }
+  }
+
+  pub fn parse_mode(self) -> Option<&'static str> {
+    match self {
+      Self::TelegramHtml => Some("HTML"),
+      Self::MarkdownV2 => Some("MarkdownV2"),
+      Self::PlainText | Self::Entities => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageEntity {
+  #[serde(rename = "type")]
+  pub kind: &'static str,
+  pub offset: usize,
+  pub length: usize,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OutgoingMessage {
+  pub text: String,
+  pub entities: Vec<MessageEntity>,
+  pub protect_content: bool,
+  // Delivered without a notification sound (`disable_notification`).
+  pub silent: bool,
+  // Lets Telegram show a link preview; off unless WEB_PAGE_PREVIEW asks for it.
+  pub link_preview: bool,
+  // Overrides TELEGRAM_CHAT_ID when a route matched.
+  pub chat_id: Option<String>,
+  // Forum topic within the chat (`message_thread_id`).
+  pub thread_id: Option<i64>,
+  pub reply_markup: Option<InlineKeyboard>,
+}
+
+// Telegram's `reply_markup` for buttons below a message, one Vec per row.
+#[derive(Debug, Clone, Serialize)]
+pub struct InlineKeyboard {
+  pub inline_keyboard: Vec<Vec<InlineButton>>,
+}
+
+// Serialized as the bare Telegram button object; a callback button (e.g.
+// "Mark read") would be another variant carrying `callback_data`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum InlineButton {
+  Url { text: String, url: String },
+}
+
+pub fn open_on_github_keyboard(url: &str) -> InlineKeyboard {
+  InlineKeyboard {
+    inline_keyboard: vec![vec![InlineButton::Url {
+      text: "Open on GitHub".to_string(),
+      url: url.to_string(),
+    }]],
+  }
+}
+
+pub const TELEGRAM_MESSAGE_LIMIT: usize = 4096;
+// Room kept free in each split part for the "\n(12/34)" marker.
+const PART_MARKER_RESERVE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OversizePolicy {
+  Split,
+  Truncate,
+}
+
+impl OversizePolicy {
+  pub fn parse(raw: &str) -> Result<Self> {
+    match raw.to_ascii_lowercase().as_str() {
+      "split" => Ok(Self::Split),
+      "truncate" => Ok(Self::Truncate),
+      _ => bail!("invalid OVERSIZE_POLICY: {raw}, use split or truncate"),
+    }
+  }
+}
+
+fn utf16_len(text: &str) -> usize {
+  text.encode_utf16().count()
+}
+
+// Final guard before sending: Telegram rejects texts over 4096 UTF-16 units.
+// Cuts prefer line boundaries so markup, which never spans lines, stays
+// balanced; only a single overlong line is cut mid-line, see cut_line.
+pub fn fit_message(
+  message: &OutgoingMessage,
+  policy: OversizePolicy,
+  format: MessageFormat,
+) -> Vec<OutgoingMessage> {
+  if utf16_len(&message.text) <= TELEGRAM_MESSAGE_LIMIT {
+    return vec![message.clone()];
+  }
+
+  match policy {
+    OversizePolicy::Truncate => {
+      let mut parts = chunk_message(message, TELEGRAM_MESSAGE_LIMIT - 1, format);
+      parts.truncate(1);
+      for part in &mut parts {
+        part.text.push('…');
+      }
+      parts
+    }
+    OversizePolicy::Split => {
+      let parts = chunk_message(
+        message,
+        TELEGRAM_MESSAGE_LIMIT - PART_MARKER_RESERVE,
+        format,
+      );
+      let total = parts.len();
+      parts
+        .into_iter()
+        .enumerate()
+        .map(|(idx, mut part)| {
+          let marker = format!("({}/{total})", idx + 1);
+          part.text.push('\n');
+          match format {
+            MessageFormat::MarkdownV2 => part.text.push_str(&escape_markdown(&marker)),
+            _ => part.text.push_str(&marker),
+          }
+          part
+        })
+        .collect()
+    }
+  }
+}
+
+fn chunk_message(
+  message: &OutgoingMessage,
+  limit: usize,
+  format: MessageFormat,
+) -> Vec<OutgoingMessage> {
+  let mut start = 0;
+  split_message(&message.text, limit, format)
+    .into_iter()
+    .map(|text| {
+      let part = message_part(message, text, start);
+      start += utf16_len(&part.text);
+      part
+    })
+    .collect()
+}
+
+// Packs whole lines into chunks of at most `limit` UTF-16 units; a line that
+// is too long by itself is cut by cut_line.
+pub fn split_message(text: &str, limit: usize, format: MessageFormat) -> Vec<String> {
+  let mut chunks = Vec::new();
+  let mut chunk = String::new();
+  for line in text.split_inclusive('\n') {
+    let pieces = if utf16_len(line) <= limit {
+      vec![line.to_string()]
+    } else {
+      cut_line(line, limit, format)
+    };
+    for piece in pieces {
+      if !chunk.is_empty() && utf16_len(&chunk) + utf16_len(&piece) > limit {
+        chunks.push(std::mem::take(&mut chunk));
+      }
+      chunk.push_str(&piece);
+    }
+  }
+  if !chunk.is_empty() {
+    chunks.push(chunk);
+  }
+  chunks
+}
+
+// Markup still open where a line is cut: `close` ends it in one piece and
+// `open` starts it again in the next, so each piece parses on its own.
+#[derive(Debug, Clone)]
+struct OpenSpan {
+  open: String,
+  close: String,
+}
+
+// Breaks an overlong line after a space where possible and otherwise between
+// units, which keep HTML tags and entities and MarkdownV2 escapes whole.
+fn cut_line(line: &str, limit: usize, format: MessageFormat) -> Vec<String> {
+  let units = markup_units(line, format);
+  let mut pieces = Vec::new();
+  let mut spans: Vec<OpenSpan> = Vec::new();
+  let mut piece = String::new();
+  // Where the piece could end after its last space, with the spans open there.
+  let mut last_space: Option<(usize, Vec<OpenSpan>)> = None;
+  // Length of the reopened markup the piece starts with.
+  let mut prefix = 0;
+
+  for (idx, unit) in units.iter().enumerate() {
+    let mut next = spans.clone();
+    track_span(&mut next, &units[idx..], format);
+    while piece.len() > prefix
+      && utf16_len(&piece) + utf16_len(unit) + closing_len(&next) > limit
+    {
+      let (at, open) = match last_space.take() {
+        Some((at, open)) if at > prefix => (at, open),
+        _ => (piece.len(), spans.clone()),
+      };
+      let rest = piece.split_off(at);
+      piece.extend(open.iter().rev().map(|span| span.close.as_str()));
+      pieces.push(std::mem::replace(
+        &mut piece,
+        open.iter().map(|span| span.open.as_str()).collect(),
+      ));
+      prefix = piece.len();
+      piece.push_str(&rest);
+    }
+    piece.push_str(unit);
+    spans = next;
+    if *unit == " " {
+      last_space = Some((piece.len(), spans.clone()));
+    }
+  }
+  pieces.push(piece);
+  pieces
+}
+
+fn closing_len(spans: &[OpenSpan]) -> usize {
+  spans.iter().map(|span| utf16_len(&span.close)).sum()
+}
+
+// Updates the open markup for the first unit of `units`; the rest is only
+// read to find where a MarkdownV2 link's text ends.
+fn track_span(spans: &mut Vec<OpenSpan>, units: &[&str], format: MessageFormat) {
+  let unit = units[0];
+  match format {
+    MessageFormat::TelegramHtml if unit.starts_with("</") => {
+      let name = tag_name(&unit[2..]);
+      if let Some(pos) = spans
+        .iter()
+        .rposition(|span| tag_name(&span.close[2..]) == name)
+      {
+        spans.remove(pos);
+      }
+    }
+    MessageFormat::TelegramHtml if unit.starts_with('<') && unit.len() > 1 => {
+      let name = tag_name(&unit[1..]);
+      spans.push(OpenSpan {
+        open: unit.to_string(),
+        close: format!("</{name}>"),
+      });
+    }
+    MessageFormat::MarkdownV2 => {
+      let in_code = spans.last().is_some_and(|span| span.open.starts_with('`'));
+      match unit {
+        "[" if !in_code => {
+          if let Some(close) = units.iter().find(|unit| unit.starts_with("](")) {
+            spans.push(OpenSpan {
+              open: unit.to_string(),
+              close: close.to_string(),
+            });
+          }
+        }
+        _ if unit.starts_with("](") => {
+          spans.retain(|span| span.open != "[");
+        }
+        "`" | "```" | "*" | "_" | "__" | "~" | "||"
+          if !in_code || unit.starts_with('`') =>
+        {
+          match spans.iter().rposition(|span| span.open == unit) {
+            Some(pos) => {
+              spans.remove(pos);
+            }
+            None => spans.push(OpenSpan {
+              open: unit.to_string(),
+              close: unit.to_string(),
+            }),
+          }
+        }
+        _ => {}
+      }
+    }
+    _ => {}
+  }
+}
+
+fn tag_name(tag: &str) -> &str {
+  let end = tag
+    .find(|ch: char| !ch.is_ascii_alphanumeric() && ch != '-')
+    .unwrap_or(tag.len());
+  &tag[..end]
+}
+
+// The line as pieces no cut may fall inside: a whole HTML tag or entity, a
+// MarkdownV2 escape, delimiter or link target, and otherwise one character.
+fn markup_units(line: &str, format: MessageFormat) -> Vec<&str> {
+  let mut units = Vec::new();
+  let mut rest = line;
+  while let Some(ch) = rest.chars().next() {
+    let len = match (format, ch) {
+      (MessageFormat::TelegramHtml, '<') => rest.find('>').map(|end| end + 1),
+      (MessageFormat::TelegramHtml, '&') => rest
+        .char_indices()
+        .skip(1)
+        .take(10)
+        .find(|(_, ch)| !ch.is_ascii_alphanumeric() && *ch != '#')
+        .filter(|(_, ch)| *ch == ';')
+        .map(|(end, _)| end + 1),
+      (MessageFormat::MarkdownV2, '\\') => {
+        rest[1..].chars().next().map(|next| 1 + next.len_utf8())
+      }
+      (MessageFormat::MarkdownV2, ']') if rest.starts_with("](") => {
+        link_target_len(rest)
+      }
+      (MessageFormat::MarkdownV2, '`') if rest.starts_with("```") => Some(3),
+      (MessageFormat::MarkdownV2, '_') if rest.starts_with("__") => Some(2),
+      (MessageFormat::MarkdownV2, '|') if rest.starts_with("||") => Some(2),
+      _ => None,
+    };
+    let len = len.unwrap_or(ch.len_utf8());
+    units.push(&rest[..len]);
+    rest = &rest[len..];
+  }
+  units
+}
+
+// `](url)` up to the first unescaped `)`.
+fn link_target_len(rest: &str) -> Option<usize> {
+  let mut escaped = false;
+  for (idx, ch) in rest.char_indices().skip(2) {
+    match ch {
+      _ if escaped => escaped = false,
+      '\\' => escaped = true,
+      ')' => return Some(idx + 1),
+      _ => {}
+    }
+  }
+  None
+}
+
+// Rebases the entities overlapping the part onto it; one that crosses a cut
+// is clipped into each part it covers.
+fn message_part(
+  message: &OutgoingMessage,
+  text: String,
+  start: usize,
+) -> OutgoingMessage {
+  let end = start + utf16_len(&text);
+  let entities = message
+    .entities
+    .iter()
+    .filter_map(|entity| {
+      let from = entity.offset.max(start);
+      let to = (entity.offset + entity.length).min(end);
+      (from < to).then(|| MessageEntity {
+        offset: from - start,
+        length: to - from,
+        ..entity.clone()
+      })
+    })
+    .collect();
+
+  OutgoingMessage {
+    text,
+    entities,
+    protect_content: message.protect_content,
+    silent: message.silent,
+    link_preview: message.link_preview,
+    chat_id: message.chat_id.clone(),
+    thread_id: message.thread_id,
+    reply_markup: message.reply_markup.clone(),
+  }
+}
+
+// Every character MarkdownV2 reserves must be backslash-escaped in text.
+fn escape_markdown(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for ch in text.chars() {
+    if "_*[]()~`>#+-=|{}.!\\".contains(ch) {
+      escaped.push('\\');
+    }
+    escaped.push(ch);
+  }
+  escaped
+}
+
+// Inside code spans and link targets only the delimiter and backslash are.
+fn escape_markdown_in(text: &str, delimiter: char) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for ch in text.chars() {
+    if ch == delimiter || ch == '\\' {
+      escaped.push('\\');
+    }
+    escaped.push(ch);
+  }
+  escaped
+}
+
+// Accumulates message text in the selected format. In entities mode the text
+// stays plain and markup is recorded as entities, whose offsets and lengths
+// Telegram counts in UTF-16 code units.
+struct MessageWriter {
+  format: MessageFormat,
+  message: OutgoingMessage,
+  utf16_len: usize,
+}
+
+impl MessageWriter {
+  fn new(format: MessageFormat) -> Self {
+    Self {
+      format,
+      message: OutgoingMessage::default(),
+      utf16_len: 0,
+    }
+  }
+
+  fn raw(&mut self, text: &str) {
+    self.message.text.push_str(text);
+    self.utf16_len += text.encode_utf16().count();
+  }
+
+  fn text(&mut self, text: &str) {
+    match self.format {
+      MessageFormat::TelegramHtml => self.raw(&html_escape::encode_safe(text)),
+      MessageFormat::MarkdownV2 => self.raw(&escape_markdown(text)),
+      MessageFormat::PlainText | MessageFormat::Entities => self.raw(text),
+    }
+  }
+
+  fn entity(&mut self, kind: &'static str, text: &str, url: Option<&str>) {
+    let offset = self.utf16_len;
+    self.raw(text);
+    self.message.entities.push(MessageEntity {
+      kind,
+      offset,
+      length: self.utf16_len - offset,
+      url: url.map(str::to_string),
+    });
+  }
+
+  fn code(&mut self, text: &str) {
+    match self.format {
+      MessageFormat::TelegramHtml => {
+        self.raw("<code>");
+        self.text(text);
+        self.raw("</code>");
+      }
+      MessageFormat::MarkdownV2 => {
+        self.raw(&format!("`{}`", escape_markdown_in(text, '`')));
+      }
+      MessageFormat::PlainText => self.raw(text),
+      MessageFormat::Entities => self.entity("code", text, None),
+    }
+  }
+
+  fn bold(&mut self, text: &str) {
+    match self.format {
+      MessageFormat::TelegramHtml => {
+        self.raw("<b>");
+        self.text(text);
+        self.raw("</b>");
+      }
+      MessageFormat::MarkdownV2 => {
+        self.raw("*");
+        self.text(text);
+        self.raw("*");
+      }
+      MessageFormat::PlainText => self.raw(text),
+      MessageFormat::Entities => self.entity("bold", text, None),
+    }
+  }
+
+  fn link(&mut self, text: &str, url: &str) {
+    match self.format {
+      MessageFormat::TelegramHtml => {
+        self.raw(&format!(
+          "<a href=\"{}\">",
+          html_escape::encode_double_quoted_attribute(url)
+        ));
+        self.text(text);
+        self.raw("</a>");
+      }
+      MessageFormat::MarkdownV2 => {
+        self.raw("[");
+        self.text(text);
+        self.raw(&format!("]({})", escape_markdown_in(url, ')')));
+      }
+      MessageFormat::PlainText => self.raw(&format!("{text} {url}")),
+      MessageFormat::Entities => self.entity("text_link", text, Some(url)),
+    }
+  }
+
+  fn newline(&mut self) {
+    self.raw("\n");
+  }
+
+  fn finish(self) -> OutgoingMessage {
+    self.message
+  }
+}
+
+pub struct RenderedNotification {
+  pub header: String,
+  // Type icon and humanized reason, shown per line when grouped into a digest.
+  pub icon: Option<String>,
+  pub reason: Option<String>,
+  pub repo: String,
+  pub title: String,
+  pub fields: Vec<(String, String)>,
+  pub url: Option<String>,
+  // Set when the subject had no resolvable page and `url`, if any, only points
+  // at the repository.
+  pub fallback_link: bool,
+  pub footer: Option<String>,
+  pub template: Option<TemplatedNotification>,
+}
+
+// MESSAGE_TEMPLATE plus the values only it shows. Repo, title and url come
+// from the rendered notification itself.
+pub struct TemplatedNotification {
+  pub template: MessageTemplate,
+  pub subject_type: String,
+  pub reason: String,
+  pub updated: String,
+}
+
+impl RenderedNotification {
+  pub fn from_notification(
+    n: &GitHubNotification,
+    html_url: Option<&str>,
+    options: &FormatOptions,
+  ) -> Self {
+    let repo = n
+      .repository
+      .full_name
+      .clone()
+      .unwrap_or_else(|| "unknown/unknown".to_string());
+    let updated = options.time_display.render(n.updated_at);
+    let mut fields = vec![("Updated".to_string(), updated.clone())];
+    if options.tag_unknown_types && !is_known_subject_type(&n.subject.r#type) {
+      fields.push(("⚠️ Unknown type".to_string(), n.subject.r#type.clone()));
+    }
+
+    let icon = options.emoji_theme.emoji_for(&n.reason, &n.subject.r#type);
+    // Without a resolved html_url, the subject's API url usually still maps to
+    // its page; only the repository link counts as a fallback.
+    let html_url = html_url.map(str::to_string).or_else(|| {
+      let api_url = n.subject.url.as_ref()?;
+      api_url_to_html_url(api_url.as_str(), &n.subject.r#type)
+    });
+    let fallback_link = html_url.is_none();
+    let url = match html_url {
+      Some(url) => Some(url),
+      None if options.hide_fallback_link => None,
+      None => n.repository.html_url.as_ref().map(|url| url.to_string()),
+    };
+
+    Self {
+      header: with_emoji(icon, "GitHub Notification"),
+      icon: icon.map(str::to_string),
+      reason: Some(humanize_reason(&n.reason)),
+      repo,
+      title: match options.max_title_chars {
+        Some(max) => truncate_chars(&n.subject.title, max),
+        None => n.subject.title.clone(),
+      },
+      fields,
+      url,
+      fallback_link,
+      footer: instance_footer(options),
+      template: options
+        .template
+        .clone()
+        .map(|template| TemplatedNotification {
+          template,
+          subject_type: n.subject.r#type.clone(),
+          reason: n.reason.clone(),
+          updated,
+        }),
+    }
+  }
+
+  pub fn render(&self, format: MessageFormat) -> OutgoingMessage {
+    let mut out = MessageWriter::new(format);
+    self.write(&mut out);
+    out.finish()
+  }
+
+  fn write(&self, out: &mut MessageWriter) {
+    if let Some(templated) = &self.template {
+      return self.write_template(templated, out);
+    }
+
+    out.text(&self.header);
+    out.newline();
+    out.raw("Repo: ");
+    if out.format == MessageFormat::Entities {
+      out.bold(&self.repo);
+    } else {
+      out.code(&self.repo);
+    }
+    out.newline();
+    out.raw("Title: ");
+    match (&self.url, out.format) {
+      (Some(url), MessageFormat::Entities) => out.link(&self.title, url),
+      _ => out.code(&self.title),
+    }
+    if self.fallback_link {
+      out.text(" (no direct link)");
+    }
+    for (label, value) in &self.fields {
+      out.newline();
+      out.text(label);
+      out.raw(": ");
+      out.code(value);
+    }
+    if let Some(url) = &self.url {
+      if out.format != MessageFormat::Entities {
+        out.newline();
+        out.text(url);
+      }
+    }
+    if let Some(footer) = &self.footer {
+      out.newline();
+      out.text(footer);
+    }
+  }
+
+  // Template text is escaped like any value, so it can't carry markup of its
+  // own; the footer still follows.
+  fn write_template(&self, templated: &TemplatedNotification, out: &mut MessageWriter) {
+    for part in templated.template.parts() {
+      match part {
+        TemplatePart::Literal(text) => out.text(text),
+        TemplatePart::Field(placeholder) => out.text(match placeholder {
+          Placeholder::Repo => &self.repo,
+          Placeholder::Type => &templated.subject_type,
+          Placeholder::Reason => &templated.reason,
+          Placeholder::Title => &self.title,
+          Placeholder::Updated => &templated.updated,
+          Placeholder::ThreadUrl => self.url.as_deref().unwrap_or_default(),
+        }),
+      }
+    }
+    if let Some(footer) = &self.footer {
+      out.newline();
+      out.text(footer);
+    }
+  }
+
+  fn write_line(&self, out: &mut MessageWriter) {
+    out.text(&with_emoji(self.icon.as_deref(), "•"));
+    out.raw(" ");
+    out.code(&self.repo);
+    out.raw(" ");
+    match &self.url {
+      Some(url) => out.link(&self.title, url),
+      None => out.text(&self.title),
+    }
+    if let Some(reason) = &self.reason {
+      out.text(&format!(" ({reason})"));
+    }
+  }
+}
+
+pub fn repo_meta_line(language: Option<&str>, stars: u64) -> String {
+  format!("{} · ★ {}", language.unwrap_or("n/a"), compact_count(stars))
+}
+
+fn compact_count(count: u64) -> String {
+  let (value, suffix) = match count {
+    0..=999 => return count.to_string(),
+    1_000..=999_999 => (count as f64 / 1_000.0, "k"),
+    _ => (count as f64 / 1_000_000.0, "M"),
+  };
+  let rounded = format!("{value:.1}");
+  format!("{}{suffix}", rounded.trim_end_matches(".0"))
+}
+
+// `Closed as: not planned` for closed issues, `State: reopened` once reopened.
+pub fn state_reason_field(
+  state: Option<&str>,
+  state_reason: Option<&str>,
+) -> Option<(String, String)> {
+  let reason = state_reason?.replace('_', " ");
+  if state == Some("closed") {
+    Some(("Closed as".to_string(), reason))
+  } else {
+    Some(("State".to_string(), reason))
+  }
+}
+
+// GITHUB_ENRICH fields for a pull request. GitHub computes mergeability in the
+// background and reports `unknown` until it is done, which is left out.
+pub fn pull_request_fields(
+  draft: Option<bool>,
+  mergeable_state: Option<&str>,
+  changed_files: Option<u64>,
+) -> Vec<(String, String)> {
+  let mut fields = Vec::new();
+  if draft == Some(true) {
+    fields.push(("Draft".to_string(), "yes".to_string()));
+  }
+  if let Some(state) = mergeable_state.filter(|state| *state != "unknown") {
+    fields.push(("Mergeable".to_string(), state.to_string()));
+  }
+  if let Some(count) = changed_files {
+    fields.push(("Files".to_string(), count.to_string()));
+  }
+  fields
+}
+
+const COMMENT_SNIPPET_CHARS: usize = 200;
+
+// A comment body on one line, cut to COMMENT_SNIPPET_CHARS characters.
+pub fn comment_snippet(body: &str) -> String {
+  let body = body.split_whitespace().collect::<Vec<_>>().join(" ");
+  truncate_chars(&body, COMMENT_SNIPPET_CHARS)
+}
+
+// Cuts on a char boundary, replacing the last kept character with `…`.
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+  if text.chars().count() <= max_chars {
+    return text.to_string();
+  }
+  let mut out: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+  out.push('…');
+  out
+}
+
+pub fn humanize_reason(reason: &str) -> String {
+  match reason {
+    "assign" => "Assigned".to_string(),
+    "ci_activity" => "CI activity".to_string(),
+    "mention" => "Mentioned".to_string(),
+    "team_mention" => "Team mentioned".to_string(),
+    other => {
+      let spaced = other.replace('_', " ");
+      let mut chars = spaced.chars();
+      match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => spaced,
+      }
+    }
+  }
+}
+
+pub fn instance_footer(options: &FormatOptions) -> Option<String> {
+  options
+    .instance_name
+    .as_ref()
+    .map(|name| format!("— via {name}"))
+}
+
+// A line of plain text, such as a TELEGRAM_ADMIN_CHAT_ID alert, escaped for
+// the selected format.
+pub fn format_notice(text: &str, format: MessageFormat) -> OutgoingMessage {
+  let mut writer = MessageWriter::new(format);
+  writer.text(text);
+  writer.finish()
+}
+
+pub fn format_message(
+  n: &GitHubNotification,
+  html_url: Option<&str>,
+  options: &FormatOptions,
+) -> OutgoingMessage {
+  RenderedNotification::from_notification(n, html_url, options).render(options.format)
+}
+
+pub fn format_group(
+  items: &[RenderedNotification],
+  options: &FormatOptions,
+) -> OutgoingMessage {
+  let mut out = MessageWriter::new(options.format);
+  out.text(&with_emoji(
+    options.emoji_theme.group_emoji(),
+    &format!("{} GitHub Notifications", items.len()),
+  ));
+  for item in items {
+    out.newline();
+    item.write_line(&mut out);
+  }
+  if let Some(footer) = instance_footer(options) {
+    out.newline();
+    out.text(&footer);
+  }
+  out.finish()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::{json, Value};
+
+  fn notification(fields: Value) -> GitHubNotification {
+    let mut n = json!({
+      "id": "1",
+      "repository": {
+        "id": 1,
+        "name": "repo",
+        "full_name": "octo/repo",
+        "url": "https://api.github.com/repos/octo/repo",
+        "html_url": "https://github.com/octo/repo",
+      },
+      "subject": {
+        "title": "Fix <b> & more",
+        "url": "https://api.github.com/repos/octo/repo/issues/7",
+        "latest_comment_url": null,
+        "type": "Issue",
+      },
+      "reason": "mention",
+      "unread": true,
+      "updated_at": "2024-01-01T00:00:00Z",
+      "last_read_at": null,
+      "url": "https://api.github.com/notifications/threads/1",
+    });
+    merge(&mut n, fields);
+    serde_json::from_value(n).unwrap()
+  }
+
+  fn merge(target: &mut Value, patch: Value) {
+    match (target, patch) {
+      (Value::Object(target), Value::Object(patch)) => {
+        for (key, value) in patch {
+          merge(target.entry(key).or_insert(Value::Null), value);
+        }
+      }
+      (target, patch) => *target = patch,
+    }
+  }
+
+  fn split(text: &str, limit: usize) -> Vec<String> {
+    split_message(text, limit, MessageFormat::PlainText)
+  }
+
+  #[test]
+  fn split_keeps_text_at_the_limit_whole() {
+    assert_eq!(split("aaaaa\nbbbb", 10), ["aaaaa\nbbbb"]);
+    assert_eq!(split("aaaaa\nbbbbb", 10), ["aaaaa\n", "bbbbb"]);
+    assert_eq!(split(&"a".repeat(10), 10), ["a".repeat(10)]);
+  }
+
+  #[test]
+  fn split_never_cuts_a_multi_byte_character() {
+    let text = "🔔".repeat(5);
+    let chunks = split(&text, 3);
+    assert_eq!(chunks, vec!["🔔"; 5]);
+    assert_eq!(chunks.concat(), text);
+  }
+
+  #[test]
+  fn split_cuts_an_oversized_line_after_spaces_then_characters() {
+    assert_eq!(split("aaa bbb ccc", 8), ["aaa bbb ", "ccc"]);
+    assert_eq!(split("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+    assert_eq!(
+      split("ab\nabcdefghij\n", 4),
+      ["ab\n", "abcd", "efgh", "ij\n"]
+    );
+  }
+
+  #[test]
+  fn html_cuts_keep_tags_and_entities_whole_and_balanced() {
+    let line = format!(
+      "<a href=\"https://example.com/a b\"><b>{}</b></a>",
+      "x &amp; y ".repeat(20)
+    );
+    let chunks = split_message(&line, 60, MessageFormat::TelegramHtml);
+    assert!(chunks.len() > 1);
+    for chunk in &chunks {
+      assert!(utf16_len(chunk) <= 60, "{chunk}");
+      assert!(chunk.starts_with("<a href=\"https://example.com/a b\"><b>"));
+      assert!(chunk.ends_with("</b></a>"), "{chunk}");
+      assert_eq!(chunk.matches('&').count(), chunk.matches("&amp;").count());
+    }
+  }
+
+  #[test]
+  fn markdown_cuts_keep_escapes_and_reopen_spans() {
+    let line = format!("*{}*", "a\\. ".repeat(30));
+    for chunk in split_message(&line, 20, MessageFormat::MarkdownV2) {
+      assert!(utf16_len(&chunk) <= 20, "{chunk}");
+      assert!(chunk.starts_with('*') && chunk.ends_with('*'), "{chunk}");
+      assert!(!chunk.trim_end_matches('*').ends_with('\\'), "{chunk}");
+    }
+
+    let line = format!("[{}](https://x.y/z\\))", "word ".repeat(20));
+    for chunk in split_message(&line, 32, MessageFormat::MarkdownV2) {
+      assert!(utf16_len(&chunk) <= 32, "{chunk}");
+      assert!(chunk.starts_with('['), "{chunk}");
+      assert!(chunk.ends_with("](https://x.y/z\\))"), "{chunk}");
+    }
+  }
+
+  #[test]
+  fn entities_crossing_a_cut_are_clipped_into_both_parts() {
+    let message = OutgoingMessage {
+      text: "aaaa bbbb cc".to_string(),
+      entities: vec![MessageEntity {
+        kind: "bold",
+        offset: 2,
+        length: 9,
+        url: None,
+      }],
+      ..OutgoingMessage::default()
+    };
+    let parts = chunk_message(&message, 5, MessageFormat::Entities);
+    let spans: Vec<_> = parts
+      .iter()
+      .map(|part| {
+        let entity = &part.entities[0];
+        (part.text.as_str(), entity.offset, entity.length)
+      })
+      .collect();
+    assert_eq!(spans, [("aaaa ", 2, 3), ("bbbb ", 0, 5), ("cc", 0, 1)]);
+  }
+
+  #[test]
+  fn html_and_plain_text_render_the_same_notification() {
+    let n = notification(json!({}));
+    let rendered =
+      RenderedNotification::from_notification(&n, None, &FormatOptions::default());
+
+    assert_eq!(
+      rendered.render(MessageFormat::TelegramHtml).text,
+      "🐞 GitHub Notification\n\
+       Repo: <code>octo&#x2F;repo</code>\n\
+       Title: <code>Fix &lt;b&gt; &amp; more</code>\n\
+       Updated: <code>2024-01-01 00:00 UTC</code>\n\
+       https:&#x2F;&#x2F;github.com&#x2F;octo&#x2F;repo&#x2F;issues&#x2F;7"
+    );
+    assert_eq!(
+      rendered.render(MessageFormat::PlainText).text,
+      "🐞 GitHub Notification\n\
+       Repo: octo/repo\n\
+       Title: Fix <b> & more\n\
+       Updated: 2024-01-01 00:00 UTC\n\
+       https://github.com/octo/repo/issues/7"
+    );
+  }
+
+  fn entity_text(message: &OutgoingMessage, entity: &MessageEntity) -> String {
+    let units: Vec<u16> = message.text.encode_utf16().collect();
+    String::from_utf16(&units[entity.offset..entity.offset + entity.length]).unwrap()
+  }
+
+  #[test]
+  fn entity_offsets_count_utf16_units() {
+    let n = notification(json!({
+      "repository": {"full_name": "octo/仓库"},
+      "subject": {"title": "🚀 Ship it <now>"},
+    }));
+    let options = FormatOptions {
+      format: MessageFormat::Entities,
+      ..FormatOptions::default()
+    };
+    let message = format_message(&n, None, &options);
+
+    assert!(message.text.contains("Title: 🚀 Ship it <now>"));
+    let spans: Vec<_> = message
+      .entities
+      .iter()
+      .map(|entity| (entity.kind, entity_text(&message, entity)))
+      .collect();
+    assert_eq!(
+      spans,
+      [
+        ("bold", "octo/仓库".to_string()),
+        ("text_link", "🚀 Ship it <now>".to_string()),
+        ("code", "2024-01-01 00:00 UTC".to_string()),
+      ]
+    );
+    // "🐞 GitHub Notification\nRepo: " is 28 characters but 29 UTF-16 units.
+    assert_eq!(message.entities[0].offset, 29);
+    assert_eq!(message.entities[1].length, 16);
+    assert_eq!(
+      message.entities[1].url.as_deref(),
+      Some("https://github.com/octo/repo/issues/7")
+    );
+  }
+
+  #[test]
+  fn digest_lines_carry_the_reason_icon() {
+    let options = FormatOptions {
+      format: MessageFormat::PlainText,
+      emoji_theme: EmojiTheme::load("reason", None, None).unwrap(),
+      ..FormatOptions::default()
+    };
+    let items: Vec<_> = [("review_requested", "Fix it"), ("team_mention", "Ship it")]
+      .into_iter()
+      .map(|(reason, title)| {
+        let n = notification(json!({"reason": reason, "subject": {"title": title}}));
+        RenderedNotification::from_notification(&n, None, &options)
+      })
+      .collect();
+
+    assert_eq!(
+      format_group(&items, &options).text,
+      "🔔 2 GitHub Notifications\n\
+       👀 • octo/repo Fix it https://github.com/octo/repo/issues/7 (Review requested)\n\
+       👤 • octo/repo Ship it https://github.com/octo/repo/issues/7 (Team mentioned)"
+    );
+  }
+
+  #[test]
+  fn repo_meta_line_shows_language_and_compact_stars() {
+    assert_eq!(repo_meta_line(Some("Rust"), 42), "Rust · ★ 42");
+    assert_eq!(repo_meta_line(Some("Go"), 1_000), "Go · ★ 1k");
+    assert_eq!(repo_meta_line(Some("Go"), 12_345), "Go · ★ 12.3k");
+    assert_eq!(repo_meta_line(None, 2_500_000), "n/a · ★ 2.5M");
+  }
+
+  fn oversized() -> OutgoingMessage {
+    let line = format!("{}\n", "界".repeat(99));
+    OutgoingMessage {
+      text: line.repeat(50),
+      ..OutgoingMessage::default()
+    }
+  }
+
+  #[test]
+  fn split_policy_numbers_every_part() {
+    let message = oversized();
+    let parts = fit_message(&message, OversizePolicy::Split, MessageFormat::PlainText);
+    assert_eq!(parts.len(), 2);
+    assert!(parts[0].text.ends_with("\n(1/2)"));
+    assert!(parts[1].text.ends_with("\n(2/2)"));
+    for part in &parts {
+      assert!(utf16_len(&part.text) <= TELEGRAM_MESSAGE_LIMIT);
+    }
+    let rejoined: String = parts
+      .iter()
+      .map(|part| part.text.rsplit_once('\n').unwrap().0)
+      .collect();
+    assert_eq!(rejoined, message.text);
+  }
+
+  #[test]
+  fn truncate_policy_keeps_the_first_part_with_an_ellipsis() {
+    let parts = fit_message(
+      &oversized(),
+      OversizePolicy::Truncate,
+      MessageFormat::PlainText,
+    );
+    assert_eq!(parts.len(), 1);
+    assert!(parts[0].text.ends_with("界\n…"));
+    assert!(utf16_len(&parts[0].text) <= TELEGRAM_MESSAGE_LIMIT);
+  }
+
+  #[test]
+  fn messages_within_the_limit_are_left_alone() {
+    let message = OutgoingMessage {
+      text: "a".repeat(TELEGRAM_MESSAGE_LIMIT),
+      ..OutgoingMessage::default()
+    };
+    for policy in [OversizePolicy::Split, OversizePolicy::Truncate] {
+      let parts = fit_message(&message, policy, MessageFormat::PlainText);
+      assert_eq!(parts.len(), 1);
+      assert_eq!(parts[0].text, message.text);
+    }
+  }
+
+  #[test]
+  fn unresolved_subjects_say_the_link_is_a_fallback() {
+    let n = notification(json!({"subject": {"url": null, "type": "CheckSuite"}}));
+    let rendered =
+      RenderedNotification::from_notification(&n, None, &FormatOptions::default());
+    assert!(rendered.fallback_link);
+    assert_eq!(
+      rendered.url.as_deref(),
+      Some("https://github.com/octo/repo")
+    );
+    let text = rendered.render(MessageFormat::PlainText).text;
+    assert!(
+      text.contains("Title: Fix <b> & more (no direct link)\n"),
+      "{text}"
+    );
+    assert!(text.contains("\nhttps://github.com/octo/repo"), "{text}");
+
+    let options = FormatOptions {
+      hide_fallback_link: true,
+      ..FormatOptions::default()
+    };
+    let rendered = RenderedNotification::from_notification(&n, None, &options);
+    assert_eq!(rendered.url, None);
+    let text = rendered.render(MessageFormat::PlainText).text;
+    assert!(text.contains("(no direct link)"), "{text}");
+    assert!(!text.contains("https://github.com/octo/repo"), "{text}");
+  }
+
+  #[test]
+  fn resolved_subjects_have_no_fallback_indicator() {
+    let n = notification(json!({}));
+    let rendered =
+      RenderedNotification::from_notification(&n, None, &FormatOptions::default());
+    assert!(!rendered.fallback_link);
+    let text = rendered.render(MessageFormat::PlainText).text;
+    assert!(!text.contains("(no direct link)"), "{text}");
+  }
+
+  #[test]
+  fn state_reasons_render_per_value() {
+    let field = |state, reason| state_reason_field(Some(state), Some(reason));
+    let closed_as = |reason: &str| Some(("Closed as".to_string(), reason.to_string()));
+    assert_eq!(field("closed", "completed"), closed_as("completed"));
+    assert_eq!(field("closed", "not_planned"), closed_as("not planned"));
+    assert_eq!(field("closed", "duplicate"), closed_as("duplicate"));
+    assert_eq!(
+      field("open", "reopened"),
+      Some(("State".to_string(), "reopened".to_string()))
+    );
+    assert_eq!(state_reason_field(Some("open"), None), None);
+    assert_eq!(state_reason_field(None, None), None);
+  }
+
+  fn title_line(title: &str, format: MessageFormat) -> String {
+    let n = notification(json!({"subject": {"title": title}}));
+    let rendered =
+      RenderedNotification::from_notification(&n, None, &FormatOptions::default());
+    let text = rendered.render(format).text;
+    text
+      .lines()
+      .find(|line| line.starts_with("Title: "))
+      .unwrap()
+      .to_string()
+  }
+
+  #[test]
+  fn html_titles_escape_every_reserved_character() {
+    for (ch, escaped) in [
+      ('<', "&lt;"),
+      ('>', "&gt;"),
+      ('&', "&amp;"),
+      ('"', "&quot;"),
+      ('\'', "&#x27;"),
+    ] {
+      assert_eq!(
+        title_line(&format!("a{ch}b"), MessageFormat::TelegramHtml),
+        format!("Title: <code>a{escaped}b</code>")
+      );
+    }
+  }
+
+  #[test]
+  fn markdown_escapes_every_reserved_character() {
+    for ch in "_*[]()~`>#+-=|{}.!\\".chars() {
+      assert_eq!(escape_markdown(&format!("a{ch}b")), format!("a\\{ch}b"));
+      // Inside the title's code span only the backtick and backslash are.
+      let expected = if ch == '`' || ch == '\\' {
+        format!("Title: `a\\{ch}b`")
+      } else {
+        format!("Title: `a{ch}b`")
+      };
+      assert_eq!(
+        title_line(&format!("a{ch}b"), MessageFormat::MarkdownV2),
+        expected
+      );
+    }
+    assert_eq!(escape_markdown("plain text"), "plain text");
+  }
+
+  #[test]
+  fn the_footer_names_the_instance_when_configured() {
+    let n = notification(json!({}));
+    let options = FormatOptions {
+      instance_name: Some("prod <eu>".to_string()),
+      ..FormatOptions::default()
+    };
+    let rendered = RenderedNotification::from_notification(&n, None, &options);
+    let last_line = |format| {
+      let text = rendered.render(format).text;
+      text.lines().last().unwrap().to_string()
+    };
+    assert_eq!(last_line(MessageFormat::PlainText), "— via prod <eu>");
+    assert_eq!(
+      last_line(MessageFormat::TelegramHtml),
+      "— via prod &lt;eu&gt;"
+    );
+
+    let rendered =
+      RenderedNotification::from_notification(&n, None, &FormatOptions::default());
+    assert!(!rendered
+      .render(MessageFormat::PlainText)
+      .text
+      .contains("— via"));
+  }
+
+  #[test]
+  fn pull_request_fields_leave_out_unknown_values() {
+    let field = |label: &str, value: &str| (label.to_string(), value.to_string());
+    assert_eq!(
+      pull_request_fields(Some(true), Some("clean"), Some(3)),
+      [
+        field("Draft", "yes"),
+        field("Mergeable", "clean"),
+        field("Files", "3")
+      ]
+    );
+    assert_eq!(
+      pull_request_fields(Some(false), Some("unknown"), None),
+      Vec::<(String, String)>::new()
+    );
+  }
+
+  #[test]
+  fn digests_split_on_whole_lines_past_the_limit() {
+    let options = FormatOptions {
+      format: MessageFormat::PlainText,
+      ..FormatOptions::default()
+    };
+    let item = |idx: usize| {
+      let title = format!("Item {idx} {}", "x".repeat(80));
+      let n = notification(json!({"subject": {"title": title}}));
+      RenderedNotification::from_notification(&n, None, &options)
+    };
+    // Grow the digest until it no longer fits in one message.
+    let mut items = vec![item(0)];
+    while utf16_len(&format_group(&items, &options).text) <= TELEGRAM_MESSAGE_LIMIT {
+      items.push(item(items.len()));
+    }
+
+    let fitting = format_group(&items[..items.len() - 1], &options);
+    let parts = fit_message(&fitting, OversizePolicy::Split, options.format);
+    assert_eq!(parts.len(), 1);
+
+    let over = format_group(&items, &options);
+    let parts = fit_message(&over, OversizePolicy::Split, options.format);
+    assert_eq!(parts.len(), 2);
+    for part in &parts {
+      assert!(utf16_len(&part.text) <= TELEGRAM_MESSAGE_LIMIT);
+    }
+    let lines = |text: &str| text.lines().filter(|line| line.contains("Item ")).count();
+    assert_eq!(
+      parts.iter().map(|part| lines(&part.text)).sum::<usize>(),
+      items.len()
+    );
+  }
+
+  fn templated(template: &str) -> String {
+    let n = notification(
+      json!({"reason": "review_requested", "subject": {"type": "PullRequest", "url": "https://api.github.com/repos/octo/repo/pulls/7"}}),
+    );
+    let options = FormatOptions {
+      format: MessageFormat::PlainText,
+      template: Some(MessageTemplate::parse(template).unwrap()),
+      ..FormatOptions::default()
+    };
+    RenderedNotification::from_notification(&n, None, &options)
+      .render(options.format)
+      .text
+  }
+
+  #[test]
+  fn templates_render_every_placeholder() {
+    assert_eq!(
+      templated(r"{repo}|{type}|{reason}|{title}|{updated}|{thread_url}"),
+      "octo/repo|PullRequest|review_requested|Fix <b> & more|\
+       2024-01-01 00:00 UTC|https://github.com/octo/repo/pull/7"
+    );
+  }
+
+  #[test]
+  fn templates_render_a_subset_of_placeholders() {
+    assert_eq!(
+      templated(r"[{repo}]\n{title}"),
+      "[octo/repo]\nFix <b> & more"
+    );
+  }
+
+  #[test]
+  fn truncation_keeps_char_boundaries() {
+    assert_eq!(truncate_chars("short", 10), "short");
+    assert_eq!(truncate_chars("exactly10!", 10), "exactly10!");
+    assert_eq!(truncate_chars("a longer sentence", 8), "a longe…");
+    assert_eq!(truncate_chars("héllo wörld", 6), "héllo…");
+    assert_eq!(truncate_chars("👍👍👍👍", 3), "👍👍…");
+  }
+
+  #[test]
+  fn comment_snippets_collapse_whitespace() {
+    assert_eq!(
+      comment_snippet("Looks\n\n  good   to me"),
+      "Looks good to me"
+    );
+    let snippet = comment_snippet(&"ab ".repeat(200));
+    assert_eq!(snippet.chars().count(), COMMENT_SNIPPET_CHARS);
+    assert!(snippet.ends_with('…'));
+  }
+
+  fn truncated_title(title: &str, max: usize) -> String {
+    let options = FormatOptions {
+      max_title_chars: Some(max),
+      ..FormatOptions::default()
+    };
+    let n = notification(json!({"subject": {"title": title}}));
+    RenderedNotification::from_notification(&n, None, &options).title
+  }
+
+  #[test]
+  fn long_titles_are_cut_without_splitting_characters() {
+    assert_eq!(truncated_title("Refactor the poll loop", 10), "Refactor …");
+    assert_eq!(truncated_title("Ship it 🚀🚀🚀", 10), "Ship it 🚀…");
+    assert_eq!(truncated_title("修复登录问题", 4), "修复登…");
+    assert_eq!(truncated_title("Short", 10), "Short");
+  }
+
+  #[test]
+  fn updated_times_render_in_the_display_timezone() {
+    let ts = DateTime::parse_from_rfc3339("2024-07-01T12:30:00Z")
+      .unwrap()
+      .with_timezone(&Utc);
+    let render =
+      |timezone, format| TimeDisplay::parse(timezone, format).unwrap().render(ts);
+    assert_eq!(render("UTC", None), "2024-07-01 12:30 UTC");
+    assert_eq!(render("America/New_York", None), "2024-07-01 08:30 EDT");
+    assert_eq!(
+      render("Asia/Tokyo", Some("%Y-%m-%dT%H:%M:%S%:z")),
+      "2024-07-01T21:30:00+09:00"
+    );
+
+    assert!(TimeDisplay::parse("Mars/Olympus", None).is_err());
+    assert!(TimeDisplay::parse("UTC", Some("%Q")).is_err());
+  }
+
+  #[test]
+  fn the_updated_field_uses_the_time_display() {
+    let options = FormatOptions {
+      time_display: TimeDisplay::parse("Europe/Berlin", Some("%H:%M %d.%m.%Y"))
+        .unwrap(),
+      ..FormatOptions::default()
+    };
+    let n = notification(json!({"updated_at": "2024-01-01T00:00:00Z"}));
+    let rendered = RenderedNotification::from_notification(&n, None, &options);
+    let text = rendered.render(MessageFormat::PlainText).text;
+    assert!(text.contains("Updated: 01:00 01.01.2024"), "{text}");
+  }
+}
@@ -0,0 +1,134 @@
+use anyhow::{bail, Context, Result};
+use octocrab::models::activity::Notification as GitHubNotification;
+use regex::Regex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupeMode {
+  Exact,
+  FuzzyTitle,
+}
+
+impl DedupeMode {
+  pub fn parse(raw: &str) -> Result<Self> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+      "exact" => Ok(Self::Exact),
+      "fuzzy_title" => Ok(Self::FuzzyTitle),
+      other => bail!("invalid DEDUPE_MODE: {other}, expected exact or fuzzy_title"),
+    }
+  }
+}
+
+// Versions, dates and other runs of digits, so `Bump x from 1.0 to 1.1` and
+// `Bump x from 1.1 to 1.2` normalize to the same title.
+const DEFAULT_PATTERNS: &str = r"\d+(?:[.\-]\d+)*";
+
+// Each match of a pattern is replaced by `#` before titles are compared.
+#[derive(Debug, Clone)]
+pub struct TitleNormalizer(Vec<Regex>);
+
+impl TitleNormalizer {
+  // `;`-separated, as commas are common inside regexes.
+  pub fn parse(raw: &str) -> Result<Self> {
+    let raw = if raw.trim().is_empty() {
+      DEFAULT_PATTERNS
+    } else {
+      raw
+    };
+    let patterns = raw
+      .split(';')
+      .map(str::trim)
+      .filter(|pattern| !pattern.is_empty())
+      .map(|pattern| {
+        Regex::new(pattern)
+          .with_context(|| format!("invalid DEDUPE_FUZZY_PATTERNS entry: {pattern}"))
+      })
+      .collect::<Result<_>>()?;
+    Ok(Self(patterns))
+  }
+
+  pub fn normalize(&self, title: &str) -> String {
+    let mut title = title.trim().to_lowercase();
+    for pattern in &self.0 {
+      title = pattern.replace_all(&title, "#").into_owned();
+    }
+    title
+  }
+}
+
+pub fn fuzzy_title_key(n: &GitHubNotification, normalizer: &TitleNormalizer) -> String {
+  format!(
+    "fuzzy_title:{}:{}",
+    n.repository.full_name.as_deref().unwrap_or_default(),
+    normalizer.normalize(&n.subject.title)
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn default_patterns_mask_versions_and_numbers() {
+    let normalizer = TitleNormalizer::parse("").unwrap();
+    assert_eq!(
+      normalizer.normalize("  Bump serde from 1.0.195 to 1.0.196 "),
+      "bump serde from # to #"
+    );
+    assert_eq!(
+      normalizer.normalize("Release 2024-01-02"),
+      normalizer.normalize("release 2024-02-03")
+    );
+    assert_ne!(
+      normalizer.normalize("Bump serde from 1.0 to 1.1"),
+      normalizer.normalize("Bump tokio from 1.0 to 1.1")
+    );
+  }
+
+  #[test]
+  fn custom_patterns_replace_the_defaults() {
+    let normalizer = TitleNormalizer::parse(r"\[.*?\]; v\d+").unwrap();
+    assert_eq!(
+      normalizer.normalize("[CI] Deploy v12 done 3"),
+      "# deploy # done 3"
+    );
+    assert!(TitleNormalizer::parse("(unclosed").is_err());
+  }
+
+  #[test]
+  fn keys_are_scoped_to_the_repository() {
+    let notification = |repo: &str| -> GitHubNotification {
+      serde_json::from_value(serde_json::json!({
+        "id": "1",
+        "repository": {"id": 1, "name": "repo", "full_name": repo,
+          "url": "https://api.github.com/repos/octo/repo"},
+        "subject": {"title": "Bump x to 1.2", "url": null,
+          "latest_comment_url": null, "type": "PullRequest"},
+        "reason": "subscribed",
+        "unread": true,
+        "updated_at": "2024-01-01T00:00:00Z",
+        "last_read_at": null,
+        "url": "https://api.github.com/notifications/threads/1",
+      }))
+      .unwrap()
+    };
+    let normalizer = TitleNormalizer::parse("").unwrap();
+    assert_eq!(
+      fuzzy_title_key(&notification("octo/repo"), &normalizer),
+      "fuzzy_title:octo/repo:bump x to #"
+    );
+    assert_ne!(
+      fuzzy_title_key(&notification("octo/repo"), &normalizer),
+      fuzzy_title_key(&notification("octo/other"), &normalizer)
+    );
+  }
+
+  #[test]
+  fn dedupe_modes_parse() {
+    assert_eq!(
+      DedupeMode::parse(" Fuzzy_Title ").unwrap(),
+      DedupeMode::FuzzyTitle
+    );
+    assert_eq!(DedupeMode::parse("exact").unwrap(), DedupeMode::Exact);
+    assert!(DedupeMode::parse("fuzzy").is_err());
+  }
+}
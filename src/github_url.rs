@@ -0,0 +1,134 @@
+use anyhow::{bail, Context, Result};
+use reqwest::Url;
+
+pub const DEFAULT_API_URL: &str = "https://api.github.com";
+pub const DEFAULT_WEB_URL: &str = "https://github.com";
+
+// An http(s) base url, returned without the trailing slash so paths can be
+// appended with `format!("{base}/...")`.
+pub fn parse_base_url(name: &str, raw: &str) -> Result<String> {
+  let url = Url::parse(raw).with_context(|| format!("invalid {name}: {raw}"))?;
+  if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+    bail!("invalid {name}: {raw}, expected an http(s) url");
+  }
+  Ok(url.as_str().trim_end_matches('/').to_string())
+}
+
+// GitHub Enterprise serves its API under `/api/v3` on the web host, while
+// github.com uses a separate `api.` host.
+pub fn web_url_for(api_url: &str) -> String {
+  if let Some(web) = api_url.strip_suffix("/api/v3") {
+    return web.to_string();
+  }
+  api_url.replacen("://api.", "://", 1)
+}
+
+// Owner, repository and number of a pull request subject url such as
+// `https://api.github.com/repos/owner/repo/pulls/5`.
+pub fn parse_pr_ref(url: &str) -> Option<(String, String, u64)> {
+  let (_, path) = url.split_once("/repos/")?;
+  let mut segments = path.trim_end_matches('/').split('/');
+  let (owner, repo) = (segments.next()?, segments.next()?);
+  if owner.is_empty() || repo.is_empty() || segments.next()? != "pulls" {
+    return None;
+  }
+  let number = segments.next()?.parse().ok()?;
+  if segments.next().is_some() {
+    return None;
+  }
+  Some((owner.to_string(), repo.to_string(), number))
+}
+
+// Browser url of a notification subject, derived from its `subject.url` API
+// url without a request, e.g. `.../repos/o/r/pulls/5` to `.../o/r/pull/5`.
+// Release API urls carry an id rather than the tag, so they map to the
+// repository's release list.
+pub fn api_url_to_html_url(url: &str, subject_type: &str) -> Option<String> {
+  let (api_base, path) = url.split_once("/repos/")?;
+  let mut segments = path.trim_end_matches('/').split('/');
+  let (owner, repo) = (segments.next()?, segments.next()?);
+  let kind = segments.next()?;
+  let id = segments.next()?;
+  if owner.is_empty() || repo.is_empty() || id.is_empty() || segments.next().is_some() {
+    return None;
+  }
+
+  let web = web_url_for(api_base);
+  let path = match (subject_type, kind) {
+    ("PullRequest", "pulls") => format!("pull/{id}"),
+    ("Issue", "issues") => format!("issues/{id}"),
+    ("Commit", "commits") => format!("commit/{id}"),
+    ("Discussion", "discussions") => format!("discussions/{id}"),
+    ("Release", "releases") => "releases".to_string(),
+    _ => return None,
+  };
+  Some(format!("{web}/{owner}/{repo}/{path}"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn pull_request_refs_come_from_well_formed_urls() {
+    assert_eq!(
+      parse_pr_ref("https://api.github.com/repos/octo/repo/pulls/5"),
+      Some(("octo".to_string(), "repo".to_string(), 5))
+    );
+    assert_eq!(
+      parse_pr_ref("https://ghe.example.com/api/v3/repos/octo/repo/pulls/12/"),
+      Some(("octo".to_string(), "repo".to_string(), 12))
+    );
+  }
+
+  #[test]
+  fn malformed_pull_request_urls_are_rejected() {
+    for url in [
+      "https://api.github.com/repos/octo/repo/issues/5",
+      "https://api.github.com/repos/octo/repo/pulls/five",
+      "https://api.github.com/repos/octo/repo/pulls/",
+      "https://api.github.com/repos/octo/repo/pulls/5/files",
+      "https://api.github.com/repos//repo/pulls/5",
+      "https://api.github.com/octo/repo/pulls/5",
+      "",
+    ] {
+      assert_eq!(parse_pr_ref(url), None, "{url}");
+    }
+  }
+
+  #[test]
+  fn subject_api_urls_map_to_their_pages() {
+    let api = "https://api.github.com/repos/octo/repo";
+    let html = |path: &str, subject_type| {
+      api_url_to_html_url(&format!("{api}/{path}"), subject_type)
+    };
+    assert_eq!(
+      html("pulls/5", "PullRequest").as_deref(),
+      Some("https://github.com/octo/repo/pull/5")
+    );
+    assert_eq!(
+      html("issues/7", "Issue").as_deref(),
+      Some("https://github.com/octo/repo/issues/7")
+    );
+    assert_eq!(
+      html("releases/123456", "Release").as_deref(),
+      Some("https://github.com/octo/repo/releases")
+    );
+    assert_eq!(html("check-suites/9", "CheckSuite"), None);
+    // The type has to agree with the url.
+    assert_eq!(html("issues/7", "PullRequest"), None);
+  }
+
+  #[test]
+  fn enterprise_api_urls_map_to_the_web_host() {
+    assert_eq!(
+      api_url_to_html_url(
+        "https://ghe.example.com/api/v3/repos/octo/repo/pulls/5",
+        "PullRequest"
+      )
+      .as_deref(),
+      Some("https://ghe.example.com/octo/repo/pull/5")
+    );
+    assert_eq!(web_url_for("https://api.github.com"), "https://github.com");
+  }
+}
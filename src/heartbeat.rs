@@ -0,0 +1,114 @@
+use chrono::{DateTime, Utc};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::info;
+
+// Running totals for the HEARTBEAT_SECONDS log line, so a quiet daemon still
+// shows it is alive.
+#[derive(Default)]
+pub struct Heartbeat {
+  state: Mutex<HeartbeatState>,
+}
+
+#[derive(Default)]
+struct HeartbeatState {
+  polls: u64,
+  forwarded: i64,
+  last_error: Option<String>,
+  cursor: Option<DateTime<Utc>>,
+}
+
+impl Heartbeat {
+  pub fn record_poll(&self, forwarded: i64, cursor: Option<DateTime<Utc>>) {
+    if let Ok(mut state) = self.state.lock() {
+      state.polls += 1;
+      state.forwarded += forwarded;
+      state.cursor = cursor.or(state.cursor);
+    }
+  }
+
+  pub fn record_error(&self, err: &anyhow::Error) {
+    if let Ok(mut state) = self.state.lock() {
+      state.polls += 1;
+      state.last_error = Some(format!("{err:#}"));
+    }
+  }
+
+  pub fn summary(&self) -> String {
+    let Ok(state) = self.state.lock() else {
+      return "heartbeat: state unavailable".to_string();
+    };
+    format!(
+      "heartbeat: polls={} forwarded={} last_error={} cursor={}",
+      state.polls,
+      state.forwarded,
+      state.last_error.as_deref().unwrap_or("none"),
+      state
+        .cursor
+        .map(|cursor| cursor.to_rfc3339())
+        .unwrap_or_else(|| "none".to_string()),
+    )
+  }
+
+  pub async fn run(&self, period: Duration) {
+    self.run_with(period, |line| info!("{line}")).await
+  }
+
+  async fn run_with(&self, period: Duration, mut emit: impl FnMut(String)) {
+    let mut ticker = tokio::time::interval(period);
+    // The first tick completes immediately; skip it so the first line comes
+    // one period after startup.
+    ticker.tick().await;
+    loop {
+      ticker.tick().await;
+      emit(self.summary());
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Arc;
+
+  #[test]
+  fn summary_accumulates_polls_and_keeps_the_last_cursor() {
+    let heartbeat = Heartbeat::default();
+    assert_eq!(
+      heartbeat.summary(),
+      "heartbeat: polls=0 forwarded=0 last_error=none cursor=none"
+    );
+    let cursor = DateTime::from_timestamp(1_704_067_200, 0);
+    heartbeat.record_poll(2, cursor);
+    heartbeat.record_error(&anyhow::anyhow!("github down"));
+    heartbeat.record_poll(1, None);
+    assert_eq!(
+      heartbeat.summary(),
+      "heartbeat: polls=3 forwarded=3 last_error=github down \
+       cursor=2024-01-01T00:00:00+00:00"
+    );
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn lines_come_once_per_period_after_startup() {
+    let heartbeat = Arc::new(Heartbeat::default());
+    let lines = Arc::new(Mutex::new(0));
+    let task = {
+      let (heartbeat, lines) = (heartbeat.clone(), lines.clone());
+      tokio::spawn(async move {
+        heartbeat
+          .run_with(Duration::from_secs(60), |_| *lines.lock().unwrap() += 1)
+          .await
+      })
+    };
+
+    let count = || *lines.lock().unwrap();
+    tokio::time::sleep(Duration::from_secs(59)).await;
+    assert_eq!(count(), 0);
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert_eq!(count(), 1);
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    assert_eq!(count(), 2);
+    task.abort();
+  }
+}
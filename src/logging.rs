@@ -0,0 +1,32 @@
+use anyhow::{bail, Result};
+use tracing_subscriber::EnvFilter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+  Text,
+  Json,
+}
+
+impl LogFormat {
+  pub fn parse(raw: &str) -> Result<Self> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+      "text" => Ok(Self::Text),
+      "json" => Ok(Self::Json),
+      other => bail!("invalid LOG_FORMAT: {other}, expected text or json"),
+    }
+  }
+}
+
+// Logs go to stderr; stdout stays reserved for CLI output and STDOUT_JSON.
+// RUST_LOG overrides the default `info` level.
+pub fn init(format: LogFormat) {
+  let filter =
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+  let builder = tracing_subscriber::fmt()
+    .with_env_filter(filter)
+    .with_writer(std::io::stderr);
+  match format {
+    LogFormat::Text => builder.init(),
+    LogFormat::Json => builder.json().init(),
+  }
+}
@@ -0,0 +1,155 @@
+use crate::db::PollStats;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const POLL_DURATION_BUCKETS: &[f64] = &[0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+// Process-lifetime totals served at `/metrics` in the Prometheus text format.
+#[derive(Default)]
+pub struct Metrics {
+  state: Mutex<MetricsState>,
+}
+
+#[derive(Default)]
+struct MetricsState {
+  totals: PollStats,
+  last_success: Option<i64>,
+  // Cumulative count per bucket, plus the implicit +Inf bucket in `polls`.
+  duration_buckets: [u64; POLL_DURATION_BUCKETS.len()],
+  duration_sum: f64,
+  polls: u64,
+}
+
+impl Metrics {
+  pub fn record_poll(&self, stats: &PollStats, duration: Duration, finished_at: i64) {
+    let Ok(mut state) = self.state.lock() else {
+      return;
+    };
+    state.totals.fetched += stats.fetched;
+    state.totals.forwarded += stats.forwarded;
+    state.totals.filtered += stats.filtered;
+    state.totals.failed += stats.failed;
+    state.last_success = Some(finished_at);
+    state.observe(duration);
+  }
+
+  pub fn record_failed_poll(&self, duration: Duration) {
+    if let Ok(mut state) = self.state.lock() {
+      state.observe(duration);
+    }
+  }
+
+  // Last successful poll and whether any poll has finished yet, for the
+  // health endpoints.
+  pub fn poll_status(&self) -> (Option<i64>, bool) {
+    match self.state.lock() {
+      Ok(state) => (state.last_success, state.polls > 0),
+      Err(_) => (None, false),
+    }
+  }
+
+  pub fn render(&self) -> String {
+    let Ok(state) = self.state.lock() else {
+      return String::new();
+    };
+    let mut out = String::new();
+    for (name, help, value) in [
+      (
+        "notify_fetched_total",
+        "Notifications fetched from GitHub.",
+        state.totals.fetched,
+      ),
+      (
+        "notify_forwarded_total",
+        "Notifications forwarded to Telegram.",
+        state.totals.forwarded,
+      ),
+      (
+        "notify_filtered_total",
+        "Notifications skipped by filters.",
+        state.totals.filtered,
+      ),
+      (
+        "notify_send_failures_total",
+        "Failed Telegram sends.",
+        state.totals.failed,
+      ),
+    ] {
+      let _ = writeln!(
+        out,
+        "# HELP {name} {help}\n# TYPE {name} counter\n{name} {value}"
+      );
+    }
+
+    let _ = writeln!(
+      out,
+      "# HELP notify_last_successful_poll_timestamp_seconds Unix time of the last \
+       successful poll.\n# TYPE notify_last_successful_poll_timestamp_seconds gauge\n\
+       notify_last_successful_poll_timestamp_seconds {}",
+      state.last_success.unwrap_or(0)
+    );
+
+    let name = "notify_poll_duration_seconds";
+    let _ = writeln!(
+      out,
+      "# HELP {name} Duration of each poll.\n# TYPE {name} histogram"
+    );
+    for (bound, count) in POLL_DURATION_BUCKETS.iter().zip(state.duration_buckets) {
+      let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {count}");
+    }
+    let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {}", state.polls);
+    let _ = writeln!(out, "{name}_sum {}", state.duration_sum);
+    let _ = writeln!(out, "{name}_count {}", state.polls);
+    out
+  }
+}
+
+impl MetricsState {
+  fn observe(&mut self, duration: Duration) {
+    let secs = duration.as_secs_f64();
+    for (bound, count) in POLL_DURATION_BUCKETS.iter().zip(&mut self.duration_buckets) {
+      if secs <= *bound {
+        *count += 1;
+      }
+    }
+    self.duration_sum += secs;
+    self.polls += 1;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn polls_add_up_to_counters_and_a_histogram() {
+    let metrics = Metrics::default();
+    let stats = PollStats {
+      fetched: 3,
+      forwarded: 2,
+      filtered: 1,
+      ..PollStats::default()
+    };
+    metrics.record_poll(&stats, Duration::from_millis(300), 1_700_000_000);
+    metrics.record_poll(&stats, Duration::from_secs(3), 1_700_000_060);
+    metrics.record_failed_poll(Duration::from_secs(90));
+
+    let out = metrics.render();
+    for line in [
+      "notify_fetched_total 6",
+      "notify_forwarded_total 4",
+      "notify_filtered_total 2",
+      "notify_send_failures_total 0",
+      "notify_last_successful_poll_timestamp_seconds 1700000060",
+      "notify_poll_duration_seconds_bucket{le=\"0.5\"} 1",
+      "notify_poll_duration_seconds_bucket{le=\"5\"} 2",
+      "notify_poll_duration_seconds_bucket{le=\"60\"} 2",
+      "notify_poll_duration_seconds_bucket{le=\"+Inf\"} 3",
+      "notify_poll_duration_seconds_count 3",
+    ] {
+      assert!(out.lines().any(|l| l == line), "missing {line}:\n{out}");
+    }
+    assert_eq!(metrics.poll_status(), (Some(1_700_000_060), true));
+  }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+// Strict minimum gap between two sends to the same chat (MIN_CHAT_INTERVAL_MS).
+// Sends wait for their slot instead of being dropped.
+#[derive(Debug, Clone, Default)]
+pub struct ChatPacer {
+  min_interval: Duration,
+  next_slot: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl ChatPacer {
+  pub fn new(min_interval: Duration) -> Self {
+    Self {
+      min_interval,
+      next_slot: Arc::default(),
+    }
+  }
+
+  // The slot is reserved before sleeping, so concurrent senders queue up
+  // behind each other rather than all waking at the same instant.
+  pub async fn wait(&self, chat_id: &str) {
+    if self.min_interval.is_zero() {
+      return;
+    }
+
+    let slot = {
+      let Ok(mut next_slot) = self.next_slot.lock() else {
+        return;
+      };
+      let now = Instant::now();
+      let slot = next_slot
+        .get(chat_id)
+        .copied()
+        .filter(|slot| *slot > now)
+        .unwrap_or(now);
+      next_slot.insert(chat_id.to_string(), slot + self.min_interval);
+      slot
+    };
+    tokio::time::sleep_until(slot).await;
+  }
+}
+
+// Fixed pause between two sends of the same poll (SEND_DELAY_MS), to stay
+// under Telegram's rate limits before it starts answering 429. A shutdown
+// cuts the pause short and skips the rest, so the remaining queue drains
+// within the grace period.
+#[derive(Debug, Clone, Default)]
+pub struct SendDelay {
+  delay: Duration,
+  interrupted: Arc<AtomicBool>,
+  notify: Arc<Notify>,
+}
+
+impl SendDelay {
+  pub fn new(delay: Duration) -> Self {
+    Self {
+      delay,
+      ..Self::default()
+    }
+  }
+
+  pub async fn wait(&self) {
+    if self.delay.is_zero() {
+      return;
+    }
+
+    // Registered before the flag is checked, so an interrupt in between
+    // still wakes us.
+    let notified = self.notify.notified();
+    tokio::pin!(notified);
+    notified.as_mut().enable();
+    if self.interrupted.load(Ordering::Relaxed) {
+      return;
+    }
+    tokio::select! {
+      _ = tokio::time::sleep(self.delay) => {}
+      _ = notified => {}
+    }
+  }
+
+  pub fn interrupt(&self) {
+    self.interrupted.store(true, Ordering::Relaxed);
+    self.notify.notify_waiters();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test(start_paused = true)]
+  async fn sends_to_one_chat_keep_the_minimum_gap() {
+    let pacer = ChatPacer::new(Duration::from_millis(500));
+    let start = Instant::now();
+    pacer.wait("100").await;
+    assert_eq!(start.elapsed(), Duration::ZERO);
+    pacer.wait("100").await;
+    assert_eq!(start.elapsed(), Duration::from_millis(500));
+    pacer.wait("100").await;
+    assert_eq!(start.elapsed(), Duration::from_millis(1000));
+
+    // Other chats have their own slots.
+    pacer.wait("200").await;
+    assert_eq!(start.elapsed(), Duration::from_millis(1000));
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn concurrent_sends_queue_behind_each_other() {
+    let pacer = ChatPacer::new(Duration::from_millis(500));
+    let start = Instant::now();
+    let wait = || async {
+      pacer.wait("100").await;
+      start.elapsed()
+    };
+    let (a, b, c) = tokio::join!(wait(), wait(), wait());
+    let mut elapsed = [a, b, c];
+    elapsed.sort();
+    assert_eq!(elapsed, [0, 500, 1000].map(Duration::from_millis));
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn an_idle_chat_sends_right_away() {
+    let pacer = ChatPacer::new(Duration::from_millis(500));
+    pacer.wait("100").await;
+    tokio::time::advance(Duration::from_secs(2)).await;
+    let start = Instant::now();
+    pacer.wait("100").await;
+    assert_eq!(start.elapsed(), Duration::ZERO);
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn send_delay_pauses_between_sends() {
+    let delay = SendDelay::new(Duration::from_millis(300));
+    let start = Instant::now();
+    delay.wait().await;
+    delay.wait().await;
+    assert_eq!(start.elapsed(), Duration::from_millis(600));
+
+    let start = Instant::now();
+    SendDelay::new(Duration::ZERO).wait().await;
+    assert_eq!(start.elapsed(), Duration::ZERO);
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn an_interrupt_cuts_the_pause_short() {
+    let delay = SendDelay::new(Duration::from_secs(10));
+    let start = Instant::now();
+    let waiting = tokio::spawn({
+      let delay = delay.clone();
+      async move { delay.wait().await }
+    });
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    delay.interrupt();
+    waiting.await.unwrap();
+    assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+    // Once interrupted, later pauses are skipped entirely.
+    delay.wait().await;
+    assert_eq!(start.elapsed(), Duration::from_secs(1));
+  }
+}
@@ -0,0 +1,212 @@
+use anyhow::{bail, Context, Result};
+use hyper_util::client::legacy::connect::proxy::Tunnel;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::proxy::matcher::Matcher;
+use hyper_util::rt::TokioIo;
+use reqwest::{ClientBuilder, NoProxy, Proxy, Url};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll};
+use tokio::net::TcpStream;
+use tower_service::Service;
+
+const TELEGRAM_HOST: &str = "api.telegram.org";
+
+// HTTP_PROXY / HTTPS_PROXY / ALL_PROXY with NO_PROXY exceptions, plus
+// TELEGRAM_PROXY for Telegram alone. Only http:// proxies are accepted, since
+// GitHub requests tunnel through them with CONNECT.
+#[derive(Debug, Clone, Default)]
+pub struct ProxySettings {
+  http: Option<Url>,
+  https: Option<Url>,
+  all: Option<Url>,
+  no_proxy: Option<String>,
+  telegram: Option<Url>,
+}
+
+impl ProxySettings {
+  pub fn parse(
+    http: Option<String>,
+    https: Option<String>,
+    all: Option<String>,
+    no_proxy: Option<String>,
+    telegram: Option<String>,
+  ) -> Result<Self> {
+    Ok(Self {
+      http: http
+        .map(|raw| parse_proxy_url("HTTP_PROXY", &raw))
+        .transpose()?,
+      https: https
+        .map(|raw| parse_proxy_url("HTTPS_PROXY", &raw))
+        .transpose()?,
+      all: all
+        .map(|raw| parse_proxy_url("ALL_PROXY", &raw))
+        .transpose()?,
+      no_proxy,
+      telegram: telegram
+        .map(|raw| parse_proxy_url("TELEGRAM_PROXY", &raw))
+        .transpose()?,
+    })
+  }
+
+  fn github_configured(&self) -> bool {
+    self.http.is_some() || self.https.is_some() || self.all.is_some()
+  }
+
+  pub fn is_configured(&self) -> bool {
+    self.github_configured() || self.telegram.is_some()
+  }
+
+  // Replaces reqwest's own environment lookup so the validated settings, and
+  // TELEGRAM_PROXY ahead of them, are the only proxies used.
+  pub fn apply(&self, mut builder: ClientBuilder) -> Result<ClientBuilder> {
+    builder = builder.no_proxy();
+    if let Some(telegram) = self.telegram.clone() {
+      builder = builder.proxy(Proxy::custom(move |url| {
+        (url.host_str() == Some(TELEGRAM_HOST)).then(|| telegram.clone())
+      }));
+    }
+
+    let no_proxy = || self.no_proxy.as_deref().and_then(NoProxy::from_string);
+    if let Some(url) = &self.http {
+      builder = builder.proxy(
+        Proxy::http(url.as_str())
+          .context("configure HTTP_PROXY")?
+          .no_proxy(no_proxy()),
+      );
+    }
+    if let Some(url) = &self.https {
+      builder = builder.proxy(
+        Proxy::https(url.as_str())
+          .context("configure HTTPS_PROXY")?
+          .no_proxy(no_proxy()),
+      );
+    }
+    if let Some(url) = &self.all {
+      builder = builder.proxy(
+        Proxy::all(url.as_str())
+          .context("configure ALL_PROXY")?
+          .no_proxy(no_proxy()),
+      );
+    }
+    Ok(builder)
+  }
+
+  // Octocrab talks to GitHub through its own hyper client; without a proxy
+  // for GitHub its connector always connects directly.
+  pub fn github_connector(&self) -> ProxyConnector {
+    let mut http = HttpConnector::new();
+    http.enforce_http(false);
+    if !self.github_configured() {
+      return ProxyConnector {
+        matcher: None,
+        http,
+      };
+    }
+
+    let mut matcher = Matcher::builder();
+    if let Some(url) = &self.all {
+      matcher = matcher.all(url.to_string());
+    }
+    if let Some(url) = &self.http {
+      matcher = matcher.http(url.to_string());
+    }
+    if let Some(url) = &self.https {
+      matcher = matcher.https(url.to_string());
+    }
+    if let Some(no_proxy) = &self.no_proxy {
+      matcher = matcher.no(no_proxy.clone());
+    }
+
+    ProxyConnector {
+      matcher: Some(Arc::new(matcher.build())),
+      http,
+    }
+  }
+}
+
+fn parse_proxy_url(name: &str, raw: &str) -> Result<Url> {
+  let url = Url::parse(raw).with_context(|| format!("invalid {name}: {raw}"))?;
+  if url.scheme() != "http" || url.host_str().is_none() {
+    bail!("invalid {name}: {raw}, expected an http:// proxy url");
+  }
+  Ok(url)
+}
+
+// Connects directly or through a CONNECT tunnel, depending on the target.
+#[derive(Clone)]
+pub struct ProxyConnector {
+  matcher: Option<Arc<Matcher>>,
+  http: HttpConnector,
+}
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+impl Service<http::Uri> for ProxyConnector {
+  type Response = TokioIo<TcpStream>;
+  type Error = BoxError;
+  type Future =
+    Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+  fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+    self.http.poll_ready(cx).map_err(Into::into)
+  }
+
+  fn call(&mut self, dst: http::Uri) -> Self::Future {
+    let mut http = self.http.clone();
+    let intercept = self
+      .matcher
+      .as_ref()
+      .and_then(|matcher| matcher.intercept(&dst));
+    let Some(intercept) = intercept else {
+      return Box::pin(async move { http.call(dst).await.map_err(Into::into) });
+    };
+
+    let mut tunnel = Tunnel::new(intercept.uri().clone(), http);
+    if let Some(auth) = intercept.basic_auth() {
+      tunnel = tunnel.with_auth(auth.clone());
+    }
+    Box::pin(async move { tunnel.call(dst).await.map_err(Into::into) })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(https: Option<&str>, telegram: Option<&str>) -> Result<ProxySettings> {
+    ProxySettings::parse(
+      None,
+      https.map(str::to_string),
+      None,
+      Some("localhost".to_string()),
+      telegram.map(str::to_string),
+    )
+  }
+
+  #[test]
+  fn only_http_proxy_urls_are_accepted() {
+    assert!(!parse(None, None).unwrap().is_configured());
+    let settings = parse(Some("http://proxy.internal:3128"), None).unwrap();
+    assert!(settings.github_configured() && settings.is_configured());
+    let settings = parse(None, Some("http://tg-proxy:8080")).unwrap();
+    assert!(!settings.github_configured() && settings.is_configured());
+
+    let err = parse(Some("socks5://proxy:1080"), None).unwrap_err();
+    assert!(err.to_string().contains("invalid HTTPS_PROXY"));
+    let err = parse(None, Some("not a url")).unwrap_err();
+    assert!(err.to_string().contains("invalid TELEGRAM_PROXY"));
+  }
+
+  #[test]
+  fn proxies_apply_to_the_client_builder() {
+    let settings =
+      parse(Some("http://proxy:3128"), Some("http://tg-proxy:8080")).unwrap();
+    assert!(settings
+      .apply(reqwest::Client::builder())
+      .unwrap()
+      .build()
+      .is_ok());
+  }
+}
@@ -0,0 +1,93 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+
+// QUIET_HOURS window such as `23:00-07:00`, in local time of `tz`. An end
+// before the start spans midnight.
+#[derive(Debug, Clone)]
+pub struct QuietHours {
+  start: NaiveTime,
+  end: NaiveTime,
+  tz: Tz,
+}
+
+impl QuietHours {
+  pub fn parse(raw: &str, tz: &str) -> Result<Self> {
+    let (start, end) = raw
+      .split_once('-')
+      .with_context(|| format!("invalid QUIET_HOURS: {raw}, expected HH:MM-HH:MM"))?;
+    let tz = tz
+      .trim()
+      .parse::<Tz>()
+      .map_err(|err| anyhow!("invalid QUIET_HOURS_TZ: {tz}: {err}"))?;
+    Ok(Self {
+      start: parse_time(start)?,
+      end: parse_time(end)?,
+      tz,
+    })
+  }
+}
+
+fn parse_time(raw: &str) -> Result<NaiveTime> {
+  let raw = raw.trim();
+  raw
+    .split_once(':')
+    .and_then(|(hour, minute)| {
+      NaiveTime::from_hms_opt(hour.parse().ok()?, minute.parse().ok()?, 0)
+    })
+    .with_context(|| format!("invalid QUIET_HOURS time: {raw}, expected HH:MM"))
+}
+
+pub fn is_quiet(now: DateTime<Utc>, window: &QuietHours) -> bool {
+  let local = now.with_timezone(&window.tz).time();
+  if window.start <= window.end {
+    window.start <= local && local < window.end
+  } else {
+    local >= window.start || local < window.end
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn at(raw: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(raw)
+      .unwrap()
+      .with_timezone(&Utc)
+  }
+
+  #[test]
+  fn daytime_windows_cover_only_their_hours() {
+    let window = QuietHours::parse("12:00-14:00", "UTC").unwrap();
+    assert!(is_quiet(at("2024-01-01T12:00:00Z"), &window));
+    assert!(is_quiet(at("2024-01-01T13:59:00Z"), &window));
+    assert!(!is_quiet(at("2024-01-01T14:00:00Z"), &window));
+    assert!(!is_quiet(at("2024-01-01T11:59:00Z"), &window));
+  }
+
+  #[test]
+  fn windows_ending_before_they_start_wrap_past_midnight() {
+    let window = QuietHours::parse("23:00-07:00", "UTC").unwrap();
+    assert!(is_quiet(at("2024-01-01T23:30:00Z"), &window));
+    assert!(is_quiet(at("2024-01-02T00:00:00Z"), &window));
+    assert!(is_quiet(at("2024-01-02T06:59:00Z"), &window));
+    assert!(!is_quiet(at("2024-01-02T07:00:00Z"), &window));
+    assert!(!is_quiet(at("2024-01-01T12:00:00Z"), &window));
+  }
+
+  #[test]
+  fn windows_follow_the_configured_timezone() {
+    // 23:30 in Shanghai is 15:30 UTC.
+    let window = QuietHours::parse("23:00-07:00", "Asia/Shanghai").unwrap();
+    assert!(is_quiet(at("2024-01-01T15:30:00Z"), &window));
+    assert!(!is_quiet(at("2024-01-01T23:30:00Z"), &window));
+  }
+
+  #[test]
+  fn malformed_windows_are_rejected() {
+    assert!(QuietHours::parse("23:00", "UTC").is_err());
+    assert!(QuietHours::parse("25:00-07:00", "UTC").is_err());
+    assert!(QuietHours::parse("23:00-07:00", "Mars/Base").is_err());
+  }
+}
@@ -0,0 +1,88 @@
+use reqwest::header::HeaderMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// At or below this many remaining requests, polling waits for the reset.
+const RATE_LIMIT_FLOOR: u64 = 5;
+
+// Throttling hints from GitHub's notification responses, collected while a
+// poll runs and taken by main when it picks the next delay.
+#[derive(Debug, Clone, Default)]
+pub struct GitHubHints(Arc<Mutex<ServerHints>>);
+
+#[derive(Debug, Default)]
+pub struct ServerHints {
+  // `X-Poll-Interval`, the minimum GitHub wants between polls.
+  pub poll_interval: Option<Duration>,
+  // `X-RateLimit-Reset` epoch seconds, set once the remaining budget is low.
+  pub rate_limit_reset: Option<i64>,
+}
+
+impl GitHubHints {
+  pub fn record(&self, headers: &HeaderMap) {
+    let header = |name: &str| {
+      headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+    };
+    let Ok(mut hints) = self.0.lock() else {
+      return;
+    };
+    if let Some(secs) = header("x-poll-interval") {
+      let interval = Duration::from_secs(secs);
+      hints.poll_interval = hints.poll_interval.max(Some(interval));
+    }
+    if header("x-ratelimit-remaining").is_some_and(|left| left <= RATE_LIMIT_FLOOR) {
+      if let Some(reset) = header("x-ratelimit-reset") {
+        hints.rate_limit_reset = hints.rate_limit_reset.max(Some(reset as i64));
+      }
+    }
+  }
+
+  pub fn take(&self) -> ServerHints {
+    self
+      .0
+      .lock()
+      .map(|mut hints| std::mem::take(&mut *hints))
+      .unwrap_or_default()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+    pairs
+      .iter()
+      .map(|(name, value)| (name.parse().unwrap(), value.parse().unwrap()))
+      .collect()
+  }
+
+  #[test]
+  fn the_longest_poll_interval_of_a_poll_wins() {
+    let hints = GitHubHints::default();
+    hints.record(&headers(&[("x-poll-interval", "60")]));
+    hints.record(&headers(&[("x-poll-interval", "120")]));
+    hints.record(&headers(&[("x-poll-interval", "30")]));
+    assert_eq!(hints.take().poll_interval, Some(Duration::from_secs(120)));
+    assert_eq!(hints.take().poll_interval, None);
+  }
+
+  #[test]
+  fn the_reset_is_only_kept_once_the_budget_runs_low() {
+    let hints = GitHubHints::default();
+    hints.record(&headers(&[
+      ("x-ratelimit-remaining", "4000"),
+      ("x-ratelimit-reset", "1700000000"),
+    ]));
+    assert_eq!(hints.take().rate_limit_reset, None);
+
+    hints.record(&headers(&[
+      ("x-ratelimit-remaining", "5"),
+      ("x-ratelimit-reset", "1700000000"),
+    ]));
+    assert_eq!(hints.take().rate_limit_reset, Some(1_700_000_000));
+  }
+}
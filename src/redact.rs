@@ -0,0 +1,66 @@
+use anyhow::{bail, Result};
+
+pub const REDACTED: &str = "[redacted]";
+
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+  repo: bool,
+  title: bool,
+  url: bool,
+}
+
+impl Redactor {
+  pub fn parse(fields: &[String]) -> Result<Self> {
+    let mut redactor = Self::default();
+    for field in fields {
+      match field.to_ascii_lowercase().as_str() {
+        "repo" => redactor.repo = true,
+        "title" => redactor.title = true,
+        "url" => redactor.url = true,
+        _ => bail!("invalid REDACT_FIELDS entry: {field}, use repo, title or url"),
+      }
+    }
+    Ok(redactor)
+  }
+
+  pub fn repo<'a>(&self, repo: &'a str) -> &'a str {
+    if self.repo {
+      REDACTED
+    } else {
+      repo
+    }
+  }
+
+  pub fn title<'a>(&self, title: &'a str) -> &'a str {
+    if self.title {
+      REDACTED
+    } else {
+      title
+    }
+  }
+
+  pub fn url<'a>(&self, url: Option<&'a str>) -> Option<&'a str> {
+    url.filter(|_| !self.url)
+  }
+}
+
+pub fn redact_secrets(text: &str, secrets: &[&str]) -> String {
+  secrets
+    .iter()
+    .filter(|secret| !secret.is_empty())
+    .fold(text.to_string(), |text, secret| text.replace(secret, "***"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn only_listed_fields_are_redacted() {
+    let redactor = Redactor::parse(&["Repo".to_string()]).unwrap();
+    assert_eq!(redactor.repo("octo/secret"), REDACTED);
+    assert_eq!(redactor.title("Fix it"), "Fix it");
+    assert_eq!(redactor.url(Some("https://x")), Some("https://x"));
+    assert!(Redactor::parse(&["body".to_string()]).is_err());
+  }
+}
@@ -0,0 +1,177 @@
+use crate::emoji::with_emoji;
+use crate::format::{
+  instance_footer, FormatOptions, OutgoingMessage, RenderedNotification,
+};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Deserialize)]
+pub struct RepoEvent {
+  pub id: String,
+  #[serde(rename = "type")]
+  pub event_type: String,
+  pub actor: EventActor,
+  pub created_at: DateTime<Utc>,
+  #[serde(default)]
+  pub payload: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventActor {
+  pub login: String,
+}
+
+// Latest event timestamp seen per watched repo. A repo without a cursor is
+// seeded from its first fetch without forwarding, so enabling the watcher
+// doesn't replay the last 90 days of events.
+pub type RepoCursors = HashMap<String, DateTime<Utc>>;
+
+pub async fn fetch_repo_events(
+  http: &Client,
+  api_url: &str,
+  github_token: &str,
+  repo: &str,
+) -> Result<Vec<RepoEvent>> {
+  let url = format!("{api_url}/repos/{repo}/events?per_page=100");
+
+  let resp = http
+    .get(url)
+    .bearer_auth(github_token)
+    .header("Accept", "application/vnd.github+json")
+    .send()
+    .await
+    .context("request github events")?;
+
+  if !resp.status().is_success() {
+    bail!("github events status={}", resp.status());
+  }
+
+  resp
+    .json::<Vec<RepoEvent>>()
+    .await
+    .context("decode github events")
+}
+
+pub fn events_since<'a>(
+  events: &'a [RepoEvent],
+  cursor: DateTime<Utc>,
+  event_types: &[String],
+) -> Vec<&'a RepoEvent> {
+  let mut selected: Vec<_> = events
+    .iter()
+    .filter(|event| event.created_at >= cursor)
+    .filter(|event| event_types.iter().any(|t| t == &event.event_type))
+    .collect();
+  selected.sort_by_key(|event| event.created_at);
+  selected
+}
+
+pub fn format_repo_event(
+  repo: &str,
+  event: &RepoEvent,
+  options: &FormatOptions,
+) -> OutgoingMessage {
+  let (emoji, header, title, url) = match event.event_type.as_str() {
+    "WatchEvent" => (
+      "⭐",
+      "New Star",
+      format!("{} starred {repo}", event.actor.login),
+      Some(format!("{}/{}", options.github_web_url, event.actor.login)),
+    ),
+    "ForkEvent" => (
+      "🍴",
+      "New Fork",
+      format!("{} forked {repo}", event.actor.login),
+      event.payload["forkee"]["html_url"]
+        .as_str()
+        .map(str::to_string),
+    ),
+    other => (
+      "📣",
+      "Repository Event",
+      format!("{other} by {}", event.actor.login),
+      None,
+    ),
+  };
+  let emoji = options.emoji_theme.group_emoji().map(|_| emoji);
+
+  RenderedNotification {
+    header: with_emoji(emoji, header),
+    icon: emoji.map(str::to_string),
+    reason: None,
+    repo: repo.to_string(),
+    title,
+    fields: Vec::new(),
+    url,
+    fallback_link: false,
+    footer: instance_footer(options),
+    template: None,
+  }
+  .render(options.format)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn event(id: &str, event_type: &str, created_at: &str) -> RepoEvent {
+    RepoEvent {
+      id: id.to_string(),
+      event_type: event_type.to_string(),
+      actor: EventActor {
+        login: "alice".to_string(),
+      },
+      created_at: created_at.parse().unwrap(),
+      payload: serde_json::json!({
+        "forkee": {"html_url": "https://github.com/alice/repo"},
+      }),
+    }
+  }
+
+  #[test]
+  fn events_since_keeps_watched_types_oldest_first() {
+    let events = [
+      event("3", "WatchEvent", "2024-01-03T00:00:00Z"),
+      event("2", "PushEvent", "2024-01-02T00:00:00Z"),
+      event("1", "ForkEvent", "2024-01-02T00:00:00Z"),
+      event("0", "WatchEvent", "2023-12-31T00:00:00Z"),
+    ];
+    let types = ["WatchEvent".to_string(), "ForkEvent".to_string()];
+    let cursor = "2024-01-01T00:00:00Z".parse().unwrap();
+
+    let ids: Vec<_> = events_since(&events, cursor, &types)
+      .into_iter()
+      .map(|event| event.id.as_str())
+      .collect();
+    assert_eq!(ids, ["1", "3"]);
+  }
+
+  #[test]
+  fn stars_and_forks_link_to_the_actor_and_the_fork() {
+    let options = FormatOptions {
+      format: crate::format::MessageFormat::PlainText,
+      ..FormatOptions::default()
+    };
+    let star = format_repo_event(
+      "octo/repo",
+      &event("1", "WatchEvent", "2024-01-01T00:00:00Z"),
+      &options,
+    );
+    assert_eq!(
+      star.text,
+      "⭐ New Star\nRepo: octo/repo\nTitle: alice starred octo/repo\n\
+       https://github.com/alice"
+    );
+    let fork = format_repo_event(
+      "octo/repo",
+      &event("2", "ForkEvent", "2024-01-01T00:00:00Z"),
+      &options,
+    );
+    assert!(fork
+      .text
+      .ends_with("alice forked octo/repo\nhttps://github.com/alice/repo"));
+  }
+}
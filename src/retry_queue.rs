@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+// How often the drain task looks for queued sends that are due.
+pub const DRAIN_INTERVAL: Duration = Duration::from_secs(30);
+
+const BASE_DELAY_SECS: i64 = 30;
+const MAX_DELAY_SECS: i64 = 3600;
+
+// A new item is due at once; after its n-th failed attempt it waits 30s * 2^n,
+// capped at an hour.
+pub fn next_retry_at(now: i64, attempts: i64) -> i64 {
+  let delay = BASE_DELAY_SECS.saturating_mul(1 << attempts.clamp(0, 7));
+  now + delay.min(MAX_DELAY_SECS)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn retries_back_off_up_to_an_hour() {
+    let delays: Vec<_> = (0..9)
+      .map(|attempts| next_retry_at(1000, attempts) - 1000)
+      .collect();
+    assert_eq!(delays, [30, 60, 120, 240, 480, 960, 1920, 3600, 3600]);
+  }
+}
@@ -0,0 +1,155 @@
+use crate::rules::matches_repo_pattern;
+use anyhow::{bail, Result};
+
+// Destination chat chosen per notification. Precedence, first match wins:
+// ROUTE_BY_LABEL in configured order, then TELEGRAM_CHAT_ROUTES in configured
+// order, then TELEGRAM_CHAT_ID.
+#[derive(Debug, Clone, Default)]
+pub struct LabelRoutes(Vec<(String, String)>);
+
+impl LabelRoutes {
+  // `label:chat_id` entries, e.g. `security:-1001234,bug:-1005678`.
+  pub fn parse(raw: &str) -> Result<Self> {
+    let mut routes = Vec::new();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+      let Some((label, chat_id)) = entry.rsplit_once(':') else {
+        bail!("invalid ROUTE_BY_LABEL entry, use label:chat_id: {entry}");
+      };
+      let (label, chat_id) = (label.trim(), chat_id.trim());
+      if label.is_empty() || chat_id.is_empty() {
+        bail!("invalid ROUTE_BY_LABEL entry, use label:chat_id: {entry}");
+      }
+      routes.push((label.to_string(), chat_id.to_string()));
+    }
+    Ok(Self(routes))
+  }
+
+  pub fn chat_ids(&self) -> impl Iterator<Item = &str> {
+    self.0.iter().map(|(_, chat_id)| chat_id.as_str())
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  // With several matching labels the earliest configured route wins, not the
+  // order GitHub lists the labels in.
+  pub fn resolve(&self, labels: &[String]) -> Option<&str> {
+    self
+      .0
+      .iter()
+      .find(|(route, _)| labels.iter().any(|label| label.eq_ignore_ascii_case(route)))
+      .map(|(_, chat_id)| chat_id.as_str())
+  }
+}
+
+#[derive(Debug, Clone)]
+struct RepoRoute {
+  pattern: String,
+  chat_id: String,
+  protect_content: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RepoRoutes(Vec<RepoRoute>);
+
+impl RepoRoutes {
+  // `pattern=chat_id` entries, e.g. `myorg/*=-1001234,someuser/repo=98765`.
+  // A `:protect` suffix sends that route's messages with protect_content.
+  pub fn parse(raw: &str) -> Result<Self> {
+    let mut routes = Vec::new();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+      let Some((pattern, target)) = entry.split_once('=') else {
+        bail!("invalid TELEGRAM_CHAT_ROUTES entry, use owner/repo=chat_id: {entry}");
+      };
+      let (chat_id, protect_content) = match target.split_once(':') {
+        Some((chat_id, flag)) if flag.trim().eq_ignore_ascii_case("protect") => {
+          (chat_id, true)
+        }
+        Some(_) => bail!(
+          "invalid TELEGRAM_CHAT_ROUTES entry, the only flag is :protect: {entry}"
+        ),
+        None => (target, false),
+      };
+      let (pattern, chat_id) = (pattern.trim(), chat_id.trim());
+      if pattern.is_empty() || chat_id.is_empty() {
+        bail!("invalid TELEGRAM_CHAT_ROUTES entry, use owner/repo=chat_id: {entry}");
+      }
+      routes.push(RepoRoute {
+        pattern: pattern.to_string(),
+        chat_id: chat_id.to_string(),
+        protect_content,
+      });
+    }
+    Ok(Self(routes))
+  }
+
+  pub fn chat_ids(&self) -> impl Iterator<Item = &str> {
+    self.0.iter().map(|route| route.chat_id.as_str())
+  }
+
+  fn find(&self, repo: &str) -> Option<&RepoRoute> {
+    self
+      .0
+      .iter()
+      .find(|route| matches_repo_pattern(&route.pattern, repo))
+  }
+
+  pub fn resolve(&self, repo: &str) -> Option<&str> {
+    self.find(repo).map(|route| route.chat_id.as_str())
+  }
+
+  // Follows the repository's route even when a label route picks the chat.
+  pub fn protects(&self, repo: &str) -> bool {
+    self.find(repo).is_some_and(|route| route.protect_content)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn labels(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+  }
+
+  #[test]
+  fn the_earliest_configured_label_route_wins() {
+    let routes = LabelRoutes::parse("security:-1001, bug:-1002").unwrap();
+    assert_eq!(routes.resolve(&labels(&["bug", "Security"])), Some("-1001"));
+    assert_eq!(routes.resolve(&labels(&["docs", "BUG"])), Some("-1002"));
+    assert_eq!(routes.resolve(&labels(&["docs"])), None);
+    assert_eq!(routes.resolve(&[]), None);
+  }
+
+  #[test]
+  fn label_routes_need_a_label_and_a_chat() {
+    assert!(LabelRoutes::parse("security").is_err());
+    assert!(LabelRoutes::parse(":-1001").is_err());
+    assert!(LabelRoutes::parse("security:").is_err());
+    assert!(LabelRoutes::parse("").unwrap().is_empty());
+  }
+
+  #[test]
+  fn repo_routes_match_in_configured_order() {
+    let routes = RepoRoutes::parse("myorg/special=-1002, myorg/*=-1001").unwrap();
+    assert_eq!(routes.resolve("myorg/special"), Some("-1002"));
+    assert_eq!(routes.resolve("MyOrg/foo"), Some("-1001"));
+    assert_eq!(routes.resolve("other/foo"), None);
+    assert!(RepoRoutes::parse("myorg/*").is_err());
+    assert!(RepoRoutes::parse("=-1001").is_err());
+  }
+
+  #[test]
+  fn repo_routes_can_protect_content() {
+    let routes =
+      RepoRoutes::parse("myorg/private=-1002:protect, myorg/*=-1001").unwrap();
+    assert_eq!(routes.resolve("myorg/private"), Some("-1002"));
+    assert!(routes.protects("myorg/private"));
+    assert!(!routes.protects("myorg/public"));
+    assert!(!routes.protects("other/repo"));
+    assert_eq!(routes.chat_ids().collect::<Vec<_>>(), ["-1002", "-1001"]);
+    assert!(RepoRoutes::parse("myorg/*=-1001:loud").is_err());
+    assert!(RepoRoutes::parse("myorg/*=:protect").is_err());
+  }
+}
@@ -0,0 +1,155 @@
+use anyhow::{bail, Result};
+use octocrab::models::activity::Notification as GitHubNotification;
+
+#[derive(Debug, Clone, Default)]
+pub struct Rule {
+  repo: Option<String>,
+  reason: Option<String>,
+  subject_type: Option<String>,
+}
+
+impl Rule {
+  pub fn matches(&self, n: &GitHubNotification) -> bool {
+    if let Some(pattern) = &self.repo {
+      let repo_name = n.repository.full_name.as_deref().unwrap_or_default();
+      if !matches_repo_pattern(pattern, repo_name) {
+        return false;
+      }
+    }
+    if let Some(reason) = &self.reason {
+      if !reason.eq_ignore_ascii_case(&n.reason) {
+        return false;
+      }
+    }
+    if let Some(subject_type) = &self.subject_type {
+      if !subject_type.eq_ignore_ascii_case(&n.subject.r#type) {
+        return false;
+      }
+    }
+    true
+  }
+}
+
+// Rules are comma-separated; each rule is `&`-joined `field=value` conditions,
+// e.g. `repo=myorg/*&reason=review_requested,type=Issue`.
+pub fn parse_rules(name: &str, raw: &str) -> Result<Vec<Rule>> {
+  let mut rules = Vec::new();
+
+  for raw_rule in raw.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+    let mut rule = Rule::default();
+    for condition in raw_rule.split('&').map(str::trim) {
+      let Some((field, value)) = condition.split_once('=') else {
+        bail!("invalid {name} condition: {condition}");
+      };
+      let value = value.trim().to_string();
+      if value.is_empty() {
+        bail!("invalid {name} condition: {condition}");
+      }
+      match field.trim() {
+        "repo" => rule.repo = Some(value),
+        "reason" => rule.reason = Some(value),
+        "type" => rule.subject_type = Some(value),
+        other => bail!("invalid {name} field: {other}"),
+      }
+    }
+    rules.push(rule);
+  }
+
+  Ok(rules)
+}
+
+pub fn matches_any(rules: &[Rule], n: &GitHubNotification) -> bool {
+  rules.iter().any(|rule| rule.matches(n))
+}
+
+pub fn matches_repo_pattern(pattern: &str, repo_name: &str) -> bool {
+  match pattern.strip_suffix("/*") {
+    Some(owner) => repo_name
+      .split_once('/')
+      .is_some_and(|(repo_owner, _)| repo_owner.eq_ignore_ascii_case(owner)),
+    None => pattern.eq_ignore_ascii_case(repo_name),
+  }
+}
+
+// `rule:priority` entries, e.g. `reason=mention:10,repo=myorg/*:5`. The first
+// matching entry wins; unmatched notifications get priority 0.
+pub fn parse_priority_rules(name: &str, raw: &str) -> Result<Vec<(Rule, i64)>> {
+  let mut rules = Vec::new();
+
+  for entry in raw.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+    let Some((raw_rule, raw_priority)) = entry.rsplit_once(':') else {
+      bail!("invalid {name} entry, expected rule:priority: {entry}");
+    };
+    let Ok(priority) = raw_priority.trim().parse::<i64>() else {
+      bail!("invalid {name} priority: {raw_priority}");
+    };
+    for rule in parse_rules(name, raw_rule)? {
+      rules.push((rule, priority));
+    }
+  }
+
+  Ok(rules)
+}
+
+pub fn priority_for(rules: &[(Rule, i64)], n: &GitHubNotification) -> i64 {
+  rules
+    .iter()
+    .find(|(rule, _)| rule.matches(n))
+    .map_or(0, |(_, priority)| *priority)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn notification(repo: &str, reason: &str, subject_type: &str) -> GitHubNotification {
+    serde_json::from_value(serde_json::json!({
+      "id": "1",
+      "repository": {
+        "id": 1,
+        "name": repo.rsplit('/').next(),
+        "full_name": repo,
+        "url": format!("https://api.github.com/repos/{repo}"),
+      },
+      "subject": {
+        "title": "Title",
+        "url": null,
+        "latest_comment_url": null,
+        "type": subject_type,
+      },
+      "reason": reason,
+      "unread": true,
+      "updated_at": "2024-01-01T00:00:00Z",
+      "last_read_at": null,
+      "url": "https://api.github.com/notifications/threads/1",
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn first_matching_priority_rule_wins() {
+    let rules = parse_priority_rules(
+      "PRIORITY_RULES",
+      "reason=mention:10,repo=octo/*&type=Issue:5,repo=octo/*:-1",
+    )
+    .unwrap();
+    assert_eq!(
+      priority_for(&rules, &notification("octo/repo", "mention", "Issue")),
+      10
+    );
+    assert_eq!(
+      priority_for(&rules, &notification("octo/repo", "comment", "Issue")),
+      5
+    );
+    assert_eq!(
+      priority_for(&rules, &notification("octo/repo", "comment", "Release")),
+      -1
+    );
+    assert_eq!(
+      priority_for(&rules, &notification("other/repo", "comment", "Issue")),
+      0
+    );
+    assert!(parse_priority_rules("PRIORITY_RULES", "reason=mention").is_err());
+    assert!(parse_priority_rules("PRIORITY_RULES", "reason=mention:high").is_err());
+  }
+}
@@ -0,0 +1,114 @@
+use crate::account::Account;
+use reqwest::Client;
+
+// Classic tokens list their scopes in `X-OAuth-Scopes`; without `notifications`
+// (or `repo`, which implies it) GitHub answers the notifications API with an
+// empty list instead of an error. Fine-grained tokens send no header and
+// can't be checked this way.
+pub fn missing_notification_scope(scopes_header: Option<&str>) -> bool {
+  let Some(header) = scopes_header else {
+    return false;
+  };
+  !header
+    .split(',')
+    .map(str::trim)
+    .any(|scope| scope == "notifications" || scope == "repo")
+}
+
+pub async fn check_token_scopes(
+  http: &Client,
+  api_url: &str,
+  accounts: &[Account],
+) -> Vec<String> {
+  let mut warnings = Vec::new();
+
+  for (idx, account) in accounts.iter().enumerate() {
+    let response = http
+      .get(format!("{api_url}/user"))
+      .bearer_auth(&account.token)
+      .header("Accept", "application/vnd.github+json")
+      .send()
+      .await;
+    let Ok(response) = response else {
+      continue;
+    };
+    let scopes = response
+      .headers()
+      .get("x-oauth-scopes")
+      .and_then(|value| value.to_str().ok());
+    if missing_notification_scope(scopes) {
+      warnings.push(format!(
+        "token for account {} lacks the notifications or repo scope (has: {}), \
+         github will return no notifications",
+        account.display_name(idx),
+        scopes.unwrap_or_default()
+      ));
+    }
+  }
+
+  warnings
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+  use tokio::net::TcpListener;
+
+  #[test]
+  fn scopes_header_needs_notifications_or_repo() {
+    assert!(!missing_notification_scope(Some("repo, read:org")));
+    assert!(!missing_notification_scope(Some("read:org,notifications")));
+    assert!(missing_notification_scope(Some("read:org, gist")));
+    assert!(missing_notification_scope(Some("")));
+    assert!(!missing_notification_scope(None));
+  }
+
+  // Answers every request with the given `X-OAuth-Scopes`.
+  async fn scopes_server(scopes: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+      while let Ok((mut stream, _)) = listener.accept().await {
+        let mut request = vec![0; 8192];
+        let _ = stream.read(&mut request).await;
+        let response = format!(
+          "HTTP/1.1 200 OK\r\nx-oauth-scopes: {scopes}\r\ncontent-length: 2\r\n\
+           connection: close\r\n\r\n{{}}"
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+      }
+    });
+    format!("http://{addr}")
+  }
+
+  fn account() -> Account {
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+    Account {
+      label: "octo".to_string(),
+      token: "ghp_test".to_string(),
+      octocrab: octocrab::Octocrab::default(),
+      login: None,
+      self_login: None,
+    }
+  }
+
+  #[tokio::test]
+  async fn tokens_without_the_scope_are_warned_about() {
+    let (http, account) = (Client::new(), account());
+    let warnings =
+      check_token_scopes(&http, &scopes_server("read:org").await, &[account]).await;
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("account octo"));
+    assert!(warnings[0].contains("has: read:org"));
+  }
+
+  #[tokio::test]
+  async fn tokens_with_the_scope_pass() {
+    let (http, account) = (Client::new(), account());
+    let warnings =
+      check_token_scopes(&http, &scopes_server("repo").await, &[account]).await;
+    assert!(warnings.is_empty());
+  }
+}
@@ -0,0 +1,87 @@
+use anyhow::{Context, Result};
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+// Ctrl-c, plus SIGTERM on unix since that is what Docker and Kubernetes send.
+// Created once so a signal arriving between waits is still seen by the next
+// `recv`.
+pub struct Shutdown {
+  #[cfg(unix)]
+  terminate: tokio::signal::unix::Signal,
+}
+
+impl Shutdown {
+  pub fn listen() -> Result<Self> {
+    Ok(Self {
+      #[cfg(unix)]
+      terminate: tokio::signal::unix::signal(
+        tokio::signal::unix::SignalKind::terminate(),
+      )
+      .context("listen for SIGTERM")?,
+    })
+  }
+
+  #[cfg(unix)]
+  pub async fn recv(&mut self) -> Result<()> {
+    tokio::select! {
+      signal = tokio::signal::ctrl_c() => signal.context("listen for ctrl-c"),
+      _ = self.terminate.recv() => Ok(()),
+    }
+  }
+
+  #[cfg(not(unix))]
+  pub async fn recv(&mut self) -> Result<()> {
+    tokio::signal::ctrl_c().await.context("listen for ctrl-c")
+  }
+}
+
+// Runs the shutdown cleanup for at most SHUTDOWN_GRACE_SECONDS; past that the
+// daemon exits anyway. Returns whether the cleanup finished in time.
+pub async fn cleanup_within(
+  grace: Duration,
+  cleanup: impl Future<Output = Result<()>>,
+) -> bool {
+  match tokio::time::timeout(grace, cleanup).await {
+    Ok(Ok(())) => true,
+    Ok(Err(err)) => {
+      warn!("shutdown cleanup failed: {err:#}");
+      true
+    }
+    Err(_) => {
+      warn!(
+        "shutdown cleanup exceeded {}s, exiting anyway",
+        grace.as_secs()
+      );
+      false
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test(start_paused = true)]
+  async fn cleanup_past_the_grace_period_is_abandoned() {
+    let started = tokio::time::Instant::now();
+    let slow = async {
+      tokio::time::sleep(Duration::from_secs(60)).await;
+      Ok(())
+    };
+    assert!(!cleanup_within(Duration::from_secs(10), slow).await);
+    assert_eq!(started.elapsed(), Duration::from_secs(10));
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn cleanup_ending_within_the_grace_period_is_done() {
+    let quick = async {
+      tokio::time::sleep(Duration::from_secs(1)).await;
+      Ok(())
+    };
+    assert!(cleanup_within(Duration::from_secs(10), quick).await);
+    assert!(
+      cleanup_within(Duration::from_secs(10), async { anyhow::bail!("boom") }).await
+    );
+  }
+}
@@ -0,0 +1,131 @@
+use anyhow::{bail, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placeholder {
+  Repo,
+  Type,
+  Reason,
+  Title,
+  Updated,
+  ThreadUrl,
+}
+
+impl Placeholder {
+  fn parse(name: &str) -> Option<Self> {
+    match name {
+      "repo" => Some(Self::Repo),
+      "type" => Some(Self::Type),
+      "reason" => Some(Self::Reason),
+      "title" => Some(Self::Title),
+      "updated" => Some(Self::Updated),
+      "thread_url" => Some(Self::ThreadUrl),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplatePart {
+  Literal(String),
+  Field(Placeholder),
+}
+
+// MESSAGE_TEMPLATE, e.g. `{repo}: {title}\n{thread_url}`. Env files can't hold
+// a line break, so `\n` stands for one; `{{` and `}}` are literal braces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageTemplate {
+  parts: Vec<TemplatePart>,
+}
+
+impl MessageTemplate {
+  pub fn parse(raw: &str) -> Result<Self> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+      match c {
+        '\\' if chars.peek() == Some(&'n') => {
+          chars.next();
+          literal.push('\n');
+        }
+        '{' if chars.peek() == Some(&'{') => {
+          chars.next();
+          literal.push('{');
+        }
+        '}' if chars.peek() == Some(&'}') => {
+          chars.next();
+          literal.push('}');
+        }
+        '{' => {
+          let mut name = String::new();
+          let mut closed = false;
+          for c in chars.by_ref() {
+            if c == '}' {
+              closed = true;
+              break;
+            }
+            name.push(c);
+          }
+          if !closed {
+            bail!("unclosed {{ in MESSAGE_TEMPLATE, write {{{{ for a literal brace");
+          }
+          let Some(placeholder) = Placeholder::parse(&name) else {
+            bail!(
+              "unknown MESSAGE_TEMPLATE placeholder {{{name}}}, use {{repo}}, \
+               {{type}}, {{reason}}, {{title}}, {{updated}} or {{thread_url}}"
+            );
+          };
+          if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+          }
+          parts.push(TemplatePart::Field(placeholder));
+        }
+        '}' => {
+          bail!("unmatched }} in MESSAGE_TEMPLATE, write }}}} for a literal brace")
+        }
+        c => literal.push(c),
+      }
+    }
+    if !literal.is_empty() {
+      parts.push(TemplatePart::Literal(literal));
+    }
+
+    Ok(Self { parts })
+  }
+
+  pub fn parts(&self) -> &[TemplatePart] {
+    &self.parts
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn placeholders_and_escapes_split_into_parts() {
+    let template =
+      MessageTemplate::parse(r"{{{repo}}}: {title}\n{thread_url}").unwrap();
+    assert_eq!(
+      template.parts(),
+      [
+        TemplatePart::Literal("{".to_string()),
+        TemplatePart::Field(Placeholder::Repo),
+        TemplatePart::Literal("}: ".to_string()),
+        TemplatePart::Field(Placeholder::Title),
+        TemplatePart::Literal("\n".to_string()),
+        TemplatePart::Field(Placeholder::ThreadUrl),
+      ]
+    );
+  }
+
+  #[test]
+  fn unknown_placeholders_fail_at_parse_time() {
+    let err = MessageTemplate::parse("{repo} {author}").unwrap_err();
+    assert!(err
+      .to_string()
+      .contains("unknown MESSAGE_TEMPLATE placeholder {author}"));
+    assert!(MessageTemplate::parse("{repo").is_err());
+    assert!(MessageTemplate::parse("repo}").is_err());
+  }
+}